@@ -13,6 +13,7 @@ use embassy_time::Duration;
 use {defmt_rtt as _, panic_probe as _};
 
 use daisy_garden::{AdcFloatParameter, AdcIntParameter, PatchInit};
+use dg_clock::{OverlapPolicy, TrainMode};
 
 #[embassy_executor::main]
 async fn main(spawner: Spawner) {
@@ -28,6 +29,7 @@ async fn main(spawner: Spawner) {
             //Note: CV out can also be used as a gate out....
             Output::new(patch_init.cv_out_1, Level::Low, Speed::Low),
             Duration::from_millis(3),
+            OverlapPolicy::Retrigger,
         ))
         .unwrap();
 
@@ -56,8 +58,9 @@ async fn clock_forward(
     clock_in: ExtiInput<'static>,
     clock_out: Output<'static>,
     duration: Duration,
+    policy: OverlapPolicy,
 ) {
-    dg_clock::clock_forward(clock_in, dg_types::Pin(clock_out), duration).await;
+    dg_clock::clock_forward(clock_in, dg_types::Pin(clock_out), duration, policy).await;
 }
 
 #[embassy_executor::task]
@@ -67,5 +70,13 @@ async fn clock_train(
     pulse_count: AdcIntParameter<'static, ADC1, PatchPinC5>,
     pulse_bpm: AdcFloatParameter<'static, ADC2, PatchPinC4>,
 ) {
-    dg_clock::clock_train(clock_in, dg_types::Pin(clock_out), pulse_count, pulse_bpm).await;
+    dg_clock::clock_train(
+        clock_in,
+        dg_types::Pin(clock_out),
+        pulse_count,
+        pulse_bpm,
+        0.0,
+        TrainMode::Fixed(OverlapPolicy::Drop),
+    )
+    .await;
 }
@@ -34,6 +34,12 @@ impl FhxCv {
     //TODO: set polarity
 }
 
+impl dg_types::CvOut for FhxCv {
+    async fn set_value(&mut self, value: u16) {
+        FhxCv::set_value(self, value).await;
+    }
+}
+
 pub struct FhxGate {
     sender: DynamicSender<'static, FhxSetMessage>,
     address: fhx::GtAddress,
@@ -70,6 +76,16 @@ impl FhxGate {
     }
 }
 
+impl dg_types::GateOut for FhxGate {
+    async fn set_high(&mut self) {
+        FhxGate::set_high(self).await;
+    }
+
+    async fn set_low(&mut self) {
+        FhxGate::set_low(self).await;
+    }
+}
+
 //
 // FHX (move to separate file?)
 //
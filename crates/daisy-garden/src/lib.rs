@@ -6,6 +6,6 @@ mod patch_init;
 
 pub use self::{
     fhx::{FhxCv, FhxGate, FhxSetMessage},
-    params::{AdcFloatParameter, AdcIntParameter},
+    params::{AdcCvIn, AdcFloatParameter, AdcIntParameter},
     patch_init::PatchInit,
 };
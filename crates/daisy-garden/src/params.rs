@@ -1,7 +1,7 @@
 use defmt::info;
 use embassy_stm32::adc::{Adc, AdcChannel, Instance};
 
-use dg_types::IntParameter;
+use dg_types::{CvIn, IntParameter};
 
 pub struct AdcIntParameter<'d, T, P>
 where
@@ -150,3 +150,37 @@ where
         res
     }
 }
+
+/// CV input read from an ADC channel, spanning the whole input range.
+pub struct AdcCvIn<'d, T, P>
+where
+    T: Instance,
+{
+    adc: Adc<'d, T>,
+    pin: P,
+}
+
+impl<'d, T, P> AdcCvIn<'d, T, P>
+where
+    T: Instance,
+    P: AdcChannel<T> + 'd,
+{
+    pub fn new(adc: Adc<'d, T>, pin: P) -> Self {
+        Self { adc, pin }
+    }
+}
+
+impl<'d, T, P> CvIn for AdcCvIn<'d, T, P>
+where
+    T: Instance,
+    P: AdcChannel<T> + 'd,
+{
+    async fn get_value(&mut self) -> u16 {
+        let Self { adc, pin } = self;
+
+        let value = adc.blocking_read(pin) as i32;
+
+        // Correct for patch.Init inputs, which are inverted and return 0-2**15
+        ((32768 - value) << 1).clamp(0, u16::MAX as i32) as u16
+    }
+}
@@ -0,0 +1,127 @@
+use embassy_time::Duration;
+use rand::Rng;
+use rand::rngs::SmallRng;
+use rand_core::{RngCore, SeedableRng};
+
+use dg_types::{ClockIn, FloatParameter, GateOut};
+
+use crate::gate::pulse;
+
+/// One of the two outputs of a [`BernoulliGate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BernoulliOutput {
+    A,
+    B,
+}
+
+impl BernoulliOutput {
+    pub fn other(self) -> Self {
+        match self {
+            BernoulliOutput::A => BernoulliOutput::B,
+            BernoulliOutput::B => BernoulliOutput::A,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BernoulliMode {
+    /// Each clock is routed to B with the given probability, and to A otherwise.
+    #[default]
+    Trigger,
+
+    /// Each clock switches to the other output with the given probability, and is routed to the
+    /// current output.
+    Toggle,
+
+    /// Like [`BernoulliMode::Trigger`], but the chosen output is held high until the other one is
+    /// chosen.
+    Latch,
+}
+
+/// Coin tosser behind [`bernoulli_gate`].
+pub struct BernoulliGate<R: RngCore> {
+    rng: R,
+    mode: BernoulliMode,
+    current: BernoulliOutput,
+}
+
+impl BernoulliGate<SmallRng> {
+    pub fn new_simple_from_rng(seed_rng: &mut impl RngCore, mode: BernoulliMode) -> Self {
+        let rng = SmallRng::from_rng(seed_rng).expect("Failed to create SmallRng from seed");
+        Self::new(rng, mode)
+    }
+
+    /// Deterministic gate, which always produces the same sequence for a given seed.
+    pub fn from_seed(seed: u64, mode: BernoulliMode) -> Self {
+        Self::new(SmallRng::seed_from_u64(seed), mode)
+    }
+}
+
+impl<R: RngCore> BernoulliGate<R> {
+    pub fn new(rng: R, mode: BernoulliMode) -> Self {
+        Self {
+            rng,
+            mode,
+            current: BernoulliOutput::A,
+        }
+    }
+
+    pub fn mode(&self) -> BernoulliMode {
+        self.mode
+    }
+
+    /// Picks the output for the next clock. `probability` is clamped to `0.0..=1.0`.
+    pub fn toss(&mut self, probability: f32) -> BernoulliOutput {
+        let hit = self.rng.r#gen::<f32>() < probability.clamp(0.0, 1.0);
+
+        self.current = match self.mode {
+            BernoulliMode::Trigger | BernoulliMode::Latch => {
+                if hit {
+                    BernoulliOutput::B
+                } else {
+                    BernoulliOutput::A
+                }
+            }
+            BernoulliMode::Toggle => {
+                if hit {
+                    self.current.other()
+                } else {
+                    self.current
+                }
+            }
+        };
+
+        self.current
+    }
+}
+
+/// Bernoulli gate: routes each incoming clock to either output, based on a coin toss.
+///
+/// In [`BernoulliMode::Latch`] mode, `duration` is ignored and the outputs are used as gates.
+pub async fn bernoulli_gate<R: RngCore>(
+    mut clock_in: impl ClockIn,
+    mut out_a: impl GateOut,
+    mut out_b: impl GateOut,
+    mut probability: impl FloatParameter,
+    mut gate: BernoulliGate<R>,
+    duration: Duration,
+) {
+    loop {
+        clock_in.wait().await;
+
+        let output = gate.toss(probability.get().await);
+
+        match (gate.mode(), output) {
+            (BernoulliMode::Latch, BernoulliOutput::A) => {
+                out_b.set_low().await;
+                out_a.set_high().await;
+            }
+            (BernoulliMode::Latch, BernoulliOutput::B) => {
+                out_a.set_low().await;
+                out_b.set_high().await;
+            }
+            (_, BernoulliOutput::A) => pulse(&mut out_a, duration).await,
+            (_, BernoulliOutput::B) => pulse(&mut out_b, duration).await,
+        }
+    }
+}
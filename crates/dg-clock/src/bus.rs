@@ -0,0 +1,87 @@
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::pubsub::{PubSubChannel, Subscriber, WaitResult};
+use embassy_time::Instant;
+
+use dg_types::ClockIn;
+
+/// Broadcasts the pulses of one [`ClockIn`] to several tasks.
+///
+/// An input pin can only be owned by a single task, so a patch where a divider and a Euclidean
+/// generator follow the same clock input feeds the input into a bus with [`ClockBus::feed`], and
+/// gives each generator a [`ClockSubscriber`]. The bus is typically stored in a `static`. `CAP`
+/// is the number of pulses buffered for slow subscribers, and `SUBS` the maximum number of
+/// subscribers.
+pub struct ClockBus<M: RawMutex, const CAP: usize, const SUBS: usize> {
+    channel: PubSubChannel<M, Instant, CAP, SUBS, 0>,
+}
+
+impl<M: RawMutex, const CAP: usize, const SUBS: usize> ClockBus<M, CAP, SUBS> {
+    pub const fn new() -> Self {
+        Self {
+            channel: PubSubChannel::new(),
+        }
+    }
+
+    /// Creates a subscriber, receiving the pulses published from now on.
+    ///
+    /// Returns `None` if the bus already has `SUBS` subscribers.
+    pub fn subscriber(&self) -> Option<ClockSubscriber<'_, M, CAP, SUBS>> {
+        let subscriber = self.channel.subscriber().ok()?;
+        Some(ClockSubscriber {
+            subscriber,
+            lagged: 0,
+        })
+    }
+
+    /// Publishes a pulse to all subscribers.
+    ///
+    /// This never waits: when the buffer is full, the oldest pulse is dropped for the subscribers
+    /// which haven't read it yet.
+    pub fn publish(&self, instant: Instant) {
+        self.channel
+            .immediate_publisher()
+            .publish_immediate(instant);
+    }
+
+    /// Publishes every pulse of `clock_in`.
+    pub async fn feed(&self, mut clock_in: impl ClockIn) {
+        loop {
+            self.publish(clock_in.wait().await);
+        }
+    }
+}
+
+impl<M: RawMutex, const CAP: usize, const SUBS: usize> Default for ClockBus<M, CAP, SUBS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// [`ClockIn`] receiving the pulses of a [`ClockBus`], with the instants of the original edges.
+///
+/// A subscriber which falls more than `CAP` pulses behind skips the oldest ones, which are
+/// counted by [`ClockSubscriber::lagged`].
+pub struct ClockSubscriber<'a, M: RawMutex, const CAP: usize, const SUBS: usize> {
+    subscriber: Subscriber<'a, M, Instant, CAP, SUBS, 0>,
+    lagged: u64,
+}
+
+impl<M: RawMutex, const CAP: usize, const SUBS: usize> ClockSubscriber<'_, M, CAP, SUBS> {
+    /// Total number of pulses missed by this subscriber.
+    pub fn lagged(&self) -> u64 {
+        self.lagged
+    }
+}
+
+impl<M: RawMutex, const CAP: usize, const SUBS: usize> ClockIn
+    for ClockSubscriber<'_, M, CAP, SUBS>
+{
+    async fn wait(&mut self) -> Instant {
+        loop {
+            match self.subscriber.next_message().await {
+                WaitResult::Message(instant) => return instant,
+                WaitResult::Lagged(count) => self.lagged += count,
+            }
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use embassy_futures::join::join;
+use embassy_futures::select::{Either, select};
+use embassy_time::{Duration, Instant, Timer};
+
+use dg_types::{ClockIn, ClockOut, FloatParameter};
+
+use crate::OverlapPolicy;
+use crate::queue::{TriggerQueue, emit_triggered};
+use crate::time::{bpm_period, scale_duration};
+
+const PULSE_WIDTH: Duration = Duration::from_millis(5);
+
+/// How often the tempo is re-read while waiting for the next tick.
+const BPM_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Free-running clock.
+///
+/// `policy` decides what happens when a tick occurs while the previous pulse is still high, which
+/// only happens at very high tempos.
+pub async fn clock(
+    mut clock_out: impl ClockOut,
+    mut pulse_pbm: impl FloatParameter,
+    policy: OverlapPolicy,
+) {
+    let triggers = TriggerQueue::new();
+
+    let ticks = async {
+        let mut ticker = VaryingTicker::new(Instant::now());
+
+        loop {
+            ticker.next(&mut pulse_pbm).await;
+            let _ = triggers.try_send(PULSE_WIDTH);
+        }
+    };
+
+    join(ticks, emit_triggered(&triggers, &mut clock_out, policy)).await;
+}
+
+/// Internal clock running at the tempo given by a BPM parameter.
+///
+/// This can be used wherever a master [`ClockIn`] is expected but no external clock is patched.
+pub struct InternalClock<P: FloatParameter> {
+    bpm: P,
+    ticker: VaryingTicker,
+}
+
+impl<P: FloatParameter> InternalClock<P> {
+    pub fn new(bpm: P) -> Self {
+        Self {
+            bpm,
+            ticker: VaryingTicker::new(Instant::now()),
+        }
+    }
+
+    /// Starts a new period at `at`, so that the clock ticks in phase with an edge received
+    /// then, e.g. from an external clock.
+    pub fn align(&mut self, at: Instant) {
+        self.ticker.align(at);
+    }
+
+    /// Drops the ticks which were due before `now`, keeping the phase.
+    pub async fn skip_missed(&mut self, now: Instant) {
+        let bpm = self.bpm.get().await;
+        self.ticker.skip_missed(now, bpm);
+    }
+}
+
+impl<P: FloatParameter> ClockIn for InternalClock<P> {
+    async fn wait(&mut self) -> Instant {
+        self.ticker.next(&mut self.bpm).await
+    }
+}
+
+/// Phase accumulator ticking at a varying tempo.
+///
+/// When the tempo changes, the elapsed part of the current period is kept and only the remaining
+/// part is scaled to the new tempo, so that moving a pot doesn't bunch or skip ticks.
+struct VaryingTicker {
+    /// Fraction of the current period elapsed at `updated_at`.
+    phase: f32,
+    period: Option<Duration>,
+    updated_at: Instant,
+}
+
+impl VaryingTicker {
+    fn new(now: Instant) -> Self {
+        Self {
+            phase: 0.0,
+            period: None,
+            updated_at: now,
+        }
+    }
+
+    /// Waits for the next tick, following tempo changes along the way, and returns its instant.
+    async fn next(&mut self, bpm: &mut impl FloatParameter) -> Instant {
+        loop {
+            let now = Instant::now();
+            let deadline = self.update(now, bpm.get().await);
+
+            if deadline <= now + BPM_POLL_INTERVAL {
+                Timer::at(deadline).await;
+                self.tick(deadline);
+                return deadline;
+            }
+
+            Timer::at(now + BPM_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Advances the phase up to `now`, then switches to `bpm`. Returns the instant of the next
+    /// tick.
+    fn update(&mut self, now: Instant, bpm: f32) -> Instant {
+        self.advance(now);
+
+        let period = bpm_period(bpm);
+        self.period = Some(period);
+
+        now + scale_duration(period, 1.0 - self.phase.min(1.0))
+    }
+
+    /// Wraps the phase around after a tick occurred at `at`.
+    ///
+    /// If whole periods were missed (e.g. because the executor was busy), they are dropped rather
+    /// than caught up with a burst of ticks.
+    fn tick(&mut self, at: Instant) {
+        self.advance(at);
+        self.phase = (self.phase - 1.0).max(0.0) % 1.0;
+    }
+
+    /// Starts a new period at `at`.
+    fn align(&mut self, at: Instant) {
+        self.phase = 0.0;
+        self.updated_at = at;
+    }
+
+    /// Advances the phase up to `now` at `bpm`, dropping whole missed periods.
+    fn skip_missed(&mut self, now: Instant, bpm: f32) {
+        self.period = Some(bpm_period(bpm));
+        self.advance(now);
+        self.phase %= 1.0;
+    }
+
+    /// Makes the next tick due at `at`.
+    fn restart(&mut self, at: Instant) {
+        self.phase = 1.0;
+        self.updated_at = at;
+    }
+
+    /// Moves the phase toward a tick at `at`, by `strength` times the phase error.
+    fn nudge(&mut self, at: Instant, strength: f32) {
+        self.advance(at);
+
+        let strength = strength.clamp(0.0, 1.0);
+        if self.phase < 0.5 {
+            // our last tick came before `at`, delay the next one
+            self.phase -= strength * self.phase;
+        } else {
+            // our next tick comes after `at`, advance it
+            self.phase += strength * (1.0 - self.phase);
+        }
+    }
+
+    fn advance(&mut self, now: Instant) {
+        if let Some(period) = self.period {
+            let elapsed = now.saturating_duration_since(self.updated_at);
+            self.phase += elapsed.as_micros() as f32 / period.as_micros().max(1) as f32;
+        }
+        self.updated_at = self.updated_at.max(now);
+    }
+}
+
+/// How [`clock_synced`] reacts to an edge on its sync input.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyncMode {
+    /// The phase is restarted: a pulse is emitted right away and the next one follows a period
+    /// later.
+    Hard,
+
+    /// The phase is nudged toward the sync edge by `strength` times the phase error (clamped to
+    /// `0.0..=1.0`), so that the clock gradually locks onto the sync input.
+    Soft { strength: f32 },
+}
+
+/// Free-running clock with an external sync/reset input.
+///
+/// `policy` is applied as in [`clock`].
+pub async fn clock_synced(
+    mut clock_out: impl ClockOut,
+    mut pulse_bpm: impl FloatParameter,
+    mut sync_in: impl ClockIn,
+    mode: SyncMode,
+    policy: OverlapPolicy,
+) {
+    let triggers = TriggerQueue::new();
+
+    let ticks = async {
+        let mut ticker = VaryingTicker::new(Instant::now());
+
+        loop {
+            match select(ticker.next(&mut pulse_bpm), sync_in.wait()).await {
+                Either::First(_) => {
+                    let _ = triggers.try_send(PULSE_WIDTH);
+                }
+                Either::Second(edge) => match mode {
+                    SyncMode::Hard => ticker.restart(edge),
+                    SyncMode::Soft { strength } => ticker.nudge(edge, strength),
+                },
+            }
+        }
+    };
+
+    join(ticks, emit_triggered(&triggers, &mut clock_out, policy)).await;
+}
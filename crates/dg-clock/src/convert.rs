@@ -0,0 +1,112 @@
+use embassy_futures::poll_once;
+use embassy_futures::select::{Either, select};
+use embassy_time::Duration;
+
+use dg_types::{ClockIn, FloatParameter, GateIn, GateOut};
+
+use crate::gate::PulseGate;
+use crate::time::{scale_duration, wait_until};
+use crate::{OverlapPolicy, PeriodMeter};
+
+/// Gate edges converted to triggers by [`gate_to_trigger`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GateEdge {
+    #[default]
+    Rising,
+    Falling,
+    Both,
+}
+
+impl GateEdge {
+    fn matches(self, high: bool) -> bool {
+        match self {
+            GateEdge::Rising => high,
+            GateEdge::Falling => !high,
+            GateEdge::Both => true,
+        }
+    }
+}
+
+/// How the length parameter of [`trigger_to_gate`] is interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GateLengthMode {
+    /// Length in milliseconds.
+    Absolute,
+
+    /// Length as a fraction of the measured input period.
+    PeriodFraction,
+}
+
+/// Emits a trigger of `duration` on the selected edges of `gate_in`.
+///
+/// A gate already high at startup isn't treated as a rising edge.
+///
+/// `policy` decides what happens when an edge arrives while the previous trigger is still high,
+/// e.g. for gates shorter than the trigger in [`GateEdge::Both`] mode.
+pub async fn gate_to_trigger(
+    mut gate_in: impl GateIn,
+    gate_out: impl GateOut,
+    edges: GateEdge,
+    duration: Duration,
+    policy: OverlapPolicy,
+) {
+    let mut gate = PulseGate::new(gate_out, policy);
+    let mut high = poll_once(gate_in.wait_for_high()).is_ready();
+
+    loop {
+        let edge = async {
+            if high {
+                gate_in.wait_for_low().await;
+            } else {
+                gate_in.wait_for_high().await;
+            }
+        };
+
+        match select(edge, wait_until(gate.deadline())).await {
+            Either::First(()) => {
+                high = !high;
+                if edges.matches(high) {
+                    gate.trigger(duration).await;
+                }
+            }
+            Either::Second(()) => gate.update().await,
+        }
+    }
+}
+
+/// Emits a gate of the length read from `length` for each incoming trigger.
+///
+/// `policy` decides what happens when a trigger arrives while the gate is still high. In
+/// [`GateLengthMode::PeriodFraction`] mode, triggers are dropped until the period is known.
+pub async fn trigger_to_gate(
+    mut clock_in: impl ClockIn,
+    gate_out: impl GateOut,
+    mut length: impl FloatParameter,
+    mode: GateLengthMode,
+    policy: OverlapPolicy,
+) {
+    let mut gate = PulseGate::new(gate_out, policy);
+    let mut meter = PeriodMeter::new();
+
+    loop {
+        match select(clock_in.wait(), wait_until(gate.deadline())).await {
+            Either::First(instant) => {
+                let period = meter.tick(instant);
+                let value = length.get().await;
+
+                let duration = match mode {
+                    GateLengthMode::Absolute => {
+                        Duration::from_micros((value.max(0.0) * 1000.0) as u64)
+                    }
+                    GateLengthMode::PeriodFraction => match period {
+                        Some(period) => scale_duration(period, value),
+                        None => continue,
+                    },
+                };
+
+                gate.trigger(duration).await;
+            }
+            Either::Second(()) => gate.update().await,
+        }
+    }
+}
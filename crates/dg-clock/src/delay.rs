@@ -0,0 +1,60 @@
+use embassy_futures::join::join;
+use embassy_time::Duration;
+
+use dg_types::{ClockIn, ClockOut, FloatParameter};
+
+use crate::PeriodMeter;
+use crate::queue::{PulseQueue, emit_scheduled};
+use crate::time::scale_duration;
+
+/// Maximum number of delayed pulses waiting to be emitted.
+pub const MAX_PENDING_PULSES: usize = 16;
+
+/// How the delay parameter of [`clock_delay`] is interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DelayMode {
+    /// Delay in milliseconds.
+    Absolute,
+
+    /// Delay as a fraction of the measured input period. Values above 1.0 delay pulses past the
+    /// next incoming one.
+    PeriodFraction,
+}
+
+/// Re-emits each incoming pulse after a delay.
+///
+/// Delays may be longer than the input period, in which case up to [`MAX_PENDING_PULSES`] pulses
+/// are kept pending. Pulses are never reordered, even when the delay shrinks. In
+/// [`DelayMode::PeriodFraction`] mode, pulses are dropped until the period is known.
+pub async fn clock_delay(
+    mut clock_in: impl ClockIn,
+    mut clock_out: impl ClockOut,
+    mut delay: impl FloatParameter,
+    mode: DelayMode,
+    duration: Duration,
+) {
+    let queue = PulseQueue::<MAX_PENDING_PULSES>::new();
+
+    let input = async {
+        let mut meter = PeriodMeter::new();
+
+        loop {
+            let instant = clock_in.wait().await;
+            let period = meter.tick(instant);
+            let value = delay.get().await;
+
+            let delay = match mode {
+                DelayMode::Absolute => Duration::from_micros((value.max(0.0) * 1000.0) as u64),
+                DelayMode::PeriodFraction => match period {
+                    Some(period) => scale_duration(period, value),
+                    None => continue,
+                },
+            };
+
+            // drop the pulse if too many are pending
+            let _ = queue.try_send(instant + delay);
+        }
+    };
+
+    join(input, emit_scheduled(&queue, &mut clock_out, duration)).await;
+}
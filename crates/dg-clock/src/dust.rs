@@ -0,0 +1,105 @@
+use embassy_futures::select::{Either, select};
+use embassy_time::{Duration, Instant, Timer};
+use rand::Rng;
+use rand::rngs::SmallRng;
+use rand_core::{RngCore, SeedableRng};
+
+use dg_types::{FloatParameter, GateOut};
+
+use crate::OverlapPolicy;
+use crate::gate::PulseGate;
+use crate::time::wait_until;
+
+/// How often the rate is re-read while waiting for the next pulse.
+const RATE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Random spacing source behind [`dust`].
+pub struct Dust<R: RngCore> {
+    rng: R,
+}
+
+impl Dust<SmallRng> {
+    pub fn new_simple_from_rng(seed_rng: &mut impl RngCore) -> Self {
+        let rng = SmallRng::from_rng(seed_rng).expect("Failed to create SmallRng from seed");
+        Self::new(rng)
+    }
+
+    /// Deterministic generator, which always produces the same spacings for a given seed.
+    pub fn from_seed(seed: u64) -> Self {
+        Self::new(SmallRng::seed_from_u64(seed))
+    }
+}
+
+impl<R: RngCore> Dust<R> {
+    pub fn new(rng: R) -> Self {
+        Self { rng }
+    }
+
+    /// Draws the spacing to the next pulse, in average periods.
+    ///
+    /// With a `regularity` of 0, spacings are exponentially distributed, so that pulses follow a
+    /// Poisson process. Higher values blend them toward a constant spacing of 1, reaching an even
+    /// clock at 1 (the value is clamped to `0.0..=1.0`). The average spacing is always 1.
+    pub fn next_spacing(&mut self, regularity: f32) -> f32 {
+        let regularity = regularity.clamp(0.0, 1.0);
+        let random = -libm::logf(1.0 - self.rng.r#gen::<f32>());
+        (1.0 - regularity) * random + regularity
+    }
+
+    /// Draws the interval to the next pulse at an average rate of `rate_hz`, if it is positive.
+    pub fn next_interval(&mut self, rate_hz: f32, regularity: f32) -> Option<Duration> {
+        let spacing = self.next_spacing(regularity);
+        (rate_hz > 0.0).then(|| seconds(spacing / rate_hz))
+    }
+}
+
+fn seconds(seconds: f32) -> Duration {
+    Duration::from_micros((seconds * 1_000_000.0) as u64)
+}
+
+/// Random trigger generator ("dust"), emitting pulses of `duration` at an average rate of `rate`
+/// (in Hz).
+///
+/// With a `regularity` of 0, pulses follow a Poisson process: they are independent of each
+/// other, and their spacing is exponentially distributed. Raising it blends the spacings toward
+/// an evenly spaced clock at the same rate, see [`Dust::next_spacing`]. The rate is re-read
+/// periodically, so that it can be modulated while waiting for a pulse, and no pulses are emitted
+/// while it is not positive. `policy` decides what happens when pulses are closer than
+/// `duration`.
+pub async fn dust<R: RngCore>(
+    gate_out: impl GateOut,
+    mut rate: impl FloatParameter,
+    mut regularity: impl FloatParameter,
+    mut dust: Dust<R>,
+    duration: Duration,
+    policy: OverlapPolicy,
+) {
+    let mut gate = PulseGate::new(gate_out, policy);
+
+    // spacing left until the next pulse, in average periods
+    let mut remaining = dust.next_spacing(regularity.get().await);
+    let mut updated_at = Instant::now();
+
+    loop {
+        let rate_hz = rate.get().await.max(0.0);
+        let poll = updated_at + RATE_POLL_INTERVAL;
+        let due = (rate_hz > 0.0)
+            .then(|| updated_at + seconds(remaining / rate_hz))
+            .filter(|due| *due <= poll);
+        let deadline = due.unwrap_or(poll);
+
+        match select(Timer::at(deadline), wait_until(gate.deadline())).await {
+            Either::First(()) => {
+                if due.is_some() {
+                    gate.trigger(duration).await;
+                    remaining = dust.next_spacing(regularity.get().await);
+                } else {
+                    let elapsed = (deadline - updated_at).as_micros() as f32 / 1_000_000.0;
+                    remaining -= elapsed * rate_hz;
+                }
+                updated_at = deadline;
+            }
+            Either::Second(()) => gate.update().await,
+        }
+    }
+}
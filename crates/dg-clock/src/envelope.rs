@@ -0,0 +1,300 @@
+use embassy_futures::select::{Either, select};
+use embassy_time::{Duration, Instant};
+
+use dg_types::{ClockIn, CvOut, FloatParameter, GateIn};
+
+use crate::time::wait_until;
+
+/// Steepness of the [`EnvelopeCurve::Exponential`] segments.
+const EXP_CURVATURE: f32 = 4.0;
+
+/// Which stages an [`Envelope`] goes through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EnvelopeMode {
+    /// Attack then decay back to zero, regardless of how long the gate is held.
+    #[default]
+    Ad,
+
+    /// Attack, held at full level while the gate is high, then release.
+    Ar,
+
+    /// Attack, decay to the sustain level held while the gate is high, then release.
+    Adsr,
+}
+
+/// Shape of the envelope segments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EnvelopeCurve {
+    #[default]
+    Linear,
+
+    /// RC-like segments, which move quickly at first and slow down as they approach their
+    /// target.
+    Exponential,
+}
+
+impl EnvelopeCurve {
+    /// Progress (`0.0..=1.0`) of a segment toward its target at `phase` (`0.0..=1.0`).
+    pub fn shape(self, phase: f32) -> f32 {
+        let phase = phase.clamp(0.0, 1.0);
+        match self {
+            EnvelopeCurve::Linear => phase,
+            EnvelopeCurve::Exponential => {
+                (1.0 - libm::expf(-EXP_CURVATURE * phase)) / (1.0 - libm::expf(-EXP_CURVATURE))
+            }
+        }
+    }
+}
+
+/// Envelope settings: attack, decay and release times in milliseconds, and sustain level in
+/// `0.0..=1.0`.
+///
+/// Each field can be any [`FloatParameter`], so that the settings are read from pots, while
+/// `Adsr<f32, f32, f32, f32>` holds plain values.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Adsr<A, D, S, R> {
+    pub attack: A,
+    pub decay: D,
+    pub sustain: S,
+    pub release: R,
+}
+
+impl<A: FloatParameter, D: FloatParameter, S: FloatParameter, R: FloatParameter> Adsr<A, D, S, R> {
+    /// Reads the current value of all settings.
+    pub async fn get(&mut self) -> Adsr<f32, f32, f32, f32> {
+        Adsr {
+            attack: self.attack.get().await,
+            decay: self.decay.get().await,
+            sustain: self.sustain.get().await,
+            release: self.release.get().await,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Idle,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+/// Envelope generator state, advanced in time by [`Envelope::advance`].
+///
+/// Each segment starts from the current level, so that retriggering or releasing mid-segment
+/// never makes the output jump.
+#[derive(Debug, Clone)]
+pub struct Envelope {
+    mode: EnvelopeMode,
+    curve: EnvelopeCurve,
+    stage: Stage,
+
+    /// Whether the gate is held high.
+    held: bool,
+
+    level: f32,
+
+    /// Level at the start of the current segment.
+    from: f32,
+
+    /// Time spent in the current segment.
+    elapsed: Duration,
+}
+
+impl Envelope {
+    pub const fn new(mode: EnvelopeMode, curve: EnvelopeCurve) -> Self {
+        Self {
+            mode,
+            curve,
+            stage: Stage::Idle,
+            held: false,
+            level: 0.0,
+            from: 0.0,
+            elapsed: Duration::from_ticks(0),
+        }
+    }
+
+    /// Current level, in `0.0..=1.0`.
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    /// Current level, scaled to the whole CV output range.
+    pub fn value(&self) -> u16 {
+        (self.level.clamp(0.0, 1.0) * u16::MAX as f32 + 0.5) as u16
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.stage == Stage::Idle
+    }
+
+    pub fn is_held(&self) -> bool {
+        self.held
+    }
+
+    /// Starts the attack on a rising gate, or the release on a falling one.
+    ///
+    /// In [`EnvelopeMode::Ad`] mode, the falling edge is ignored.
+    pub fn gate(&mut self, high: bool) {
+        self.held = high;
+
+        if high {
+            self.enter(Stage::Attack);
+        } else if self.mode != EnvelopeMode::Ad && self.stage != Stage::Idle {
+            self.enter(Stage::Release);
+        }
+    }
+
+    /// Starts the attack for a trigger, which has no gate length: in [`EnvelopeMode::Ar`] and
+    /// [`EnvelopeMode::Adsr`] modes, the release follows the attack right away.
+    pub fn trigger(&mut self) {
+        self.held = false;
+        self.enter(Stage::Attack);
+    }
+
+    /// Advances the envelope by `elapsed` and returns the new level.
+    pub fn advance(&mut self, elapsed: Duration, settings: &Adsr<f32, f32, f32, f32>) -> f32 {
+        let sustain = settings.sustain.clamp(0.0, 1.0);
+        let (time, target) = match self.stage {
+            Stage::Idle => return self.level,
+            Stage::Attack => (settings.attack, 1.0),
+            Stage::Decay => match self.mode {
+                EnvelopeMode::Adsr => (settings.decay, sustain),
+                _ => (settings.decay, 0.0),
+            },
+            Stage::Sustain => {
+                self.level = match self.mode {
+                    EnvelopeMode::Adsr => sustain,
+                    _ => 1.0,
+                };
+                return self.level;
+            }
+            Stage::Release => (settings.release, 0.0),
+        };
+
+        self.elapsed += elapsed;
+        let phase = if time > 0.0 {
+            self.elapsed.as_micros() as f32 / (time * 1000.0)
+        } else {
+            1.0
+        };
+        self.level = self.from + (target - self.from) * self.curve.shape(phase);
+
+        if phase >= 1.0 {
+            self.level = target;
+
+            // the time past the end of the stage is carried into the next one
+            let length = Duration::from_micros((time.max(0.0) * 1000.0) as u64);
+            let overshoot = self.elapsed.checked_sub(length).unwrap_or_default();
+
+            self.enter(self.next_stage());
+            if overshoot > Duration::from_ticks(0) {
+                return self.advance(overshoot, settings);
+            }
+        }
+
+        self.level
+    }
+
+    /// Stage following the current one once it is complete.
+    fn next_stage(&self) -> Stage {
+        match (self.stage, self.mode) {
+            (Stage::Attack, EnvelopeMode::Ad) => Stage::Decay,
+            (Stage::Attack, _) if !self.held => Stage::Release,
+            (Stage::Attack, EnvelopeMode::Ar) => Stage::Sustain,
+            (Stage::Attack, EnvelopeMode::Adsr) => Stage::Decay,
+            (Stage::Decay, EnvelopeMode::Adsr) if self.held => Stage::Sustain,
+            (Stage::Decay, EnvelopeMode::Adsr) => Stage::Release,
+            _ => Stage::Idle,
+        }
+    }
+
+    fn enter(&mut self, stage: Stage) {
+        self.stage = stage;
+        self.from = self.level;
+        self.elapsed = Duration::from_ticks(0);
+    }
+}
+
+enum Event {
+    Trigger,
+    Gate(bool),
+}
+
+/// Gate-driven envelope generator.
+///
+/// The envelope starts its attack on every rising edge of `gate_in` and its release on the
+/// falling one. While it is moving, `cv_out` is updated every `update_interval`, and the
+/// settings are re-read from `adsr` on each update.
+pub async fn envelope(
+    mut gate_in: impl GateIn,
+    cv_out: impl CvOut,
+    envelope: Envelope,
+    adsr: Adsr<impl FloatParameter, impl FloatParameter, impl FloatParameter, impl FloatParameter>,
+    update_interval: Duration,
+) {
+    let events = async |held| {
+        if held {
+            gate_in.wait_for_low().await;
+        } else {
+            gate_in.wait_for_high().await;
+        }
+        Event::Gate(!held)
+    };
+
+    run_envelope(events, cv_out, envelope, adsr, update_interval).await;
+}
+
+/// Clock-driven envelope generator.
+///
+/// Same as [`envelope`], but each incoming clock triggers the envelope (see
+/// [`Envelope::trigger`]).
+pub async fn clock_envelope(
+    mut clock_in: impl ClockIn,
+    cv_out: impl CvOut,
+    envelope: Envelope,
+    adsr: Adsr<impl FloatParameter, impl FloatParameter, impl FloatParameter, impl FloatParameter>,
+    update_interval: Duration,
+) {
+    let events = async |_| {
+        clock_in.wait().await;
+        Event::Trigger
+    };
+
+    run_envelope(events, cv_out, envelope, adsr, update_interval).await;
+}
+
+async fn run_envelope(
+    mut events: impl AsyncFnMut(bool) -> Event,
+    mut cv_out: impl CvOut,
+    mut envelope: Envelope,
+    mut adsr: Adsr<
+        impl FloatParameter,
+        impl FloatParameter,
+        impl FloatParameter,
+        impl FloatParameter,
+    >,
+    update_interval: Duration,
+) {
+    let mut updated_at = Instant::now();
+    cv_out.set_value(envelope.value()).await;
+
+    loop {
+        // no need to update the output while the envelope is idle
+        let deadline = (!envelope.is_idle()).then_some(updated_at + update_interval);
+        let event = select(events(envelope.is_held()), wait_until(deadline)).await;
+
+        let now = Instant::now();
+        envelope.advance(now - updated_at, &adsr.get().await);
+        updated_at = now;
+
+        match event {
+            Either::First(Event::Trigger) => envelope.trigger(),
+            Either::First(Event::Gate(high)) => envelope.gate(high),
+            Either::Second(()) => {}
+        }
+
+        cv_out.set_value(envelope.value()).await;
+    }
+}
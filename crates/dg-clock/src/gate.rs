@@ -0,0 +1,133 @@
+use embassy_time::{Duration, Instant, Timer};
+
+use dg_types::GateOut;
+
+/// Low time inserted between two pulses, so that downstream modules see distinct edges.
+pub(crate) const PULSE_GAP: Duration = Duration::from_millis(1);
+
+/// Maximum number of pulses waiting in [`OverlapPolicy::Queue`] mode.
+pub const MAX_QUEUED_PULSES: u32 = 16;
+
+/// What happens when a pulse is triggered while the previous one is still high.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverlapPolicy {
+    /// The new pulse is dropped.
+    #[default]
+    Drop,
+
+    /// The current pulse is cut short and a new, full-length one is started.
+    Retrigger,
+
+    /// The current pulse is extended, so that it ends a full pulse length after the new trigger.
+    Extend,
+
+    /// The new pulse is emitted once the current one is over (up to [`MAX_QUEUED_PULSES`]).
+    Queue,
+}
+
+/// Emits a single pulse on a gate output.
+pub(crate) async fn pulse(gate: &mut impl GateOut, duration: Duration) {
+    gate.set_high().await;
+    Timer::after(duration).await;
+    gate.set_low().await;
+}
+
+enum State {
+    Low,
+    High { until: Instant },
+    Gap { until: Instant },
+}
+
+/// Gate output emitting pulses according to an [`OverlapPolicy`].
+///
+/// Unlike [`dg_types::ClockOut::emit_pulse`], triggering a pulse doesn't block until its end, so
+/// that the caller can keep listening to its inputs. The caller must call
+/// [`PulseGate::update`] whenever [`PulseGate::deadline`] is reached.
+pub(crate) struct PulseGate<G: GateOut> {
+    gate: G,
+    policy: OverlapPolicy,
+    state: State,
+    queued: u32,
+    duration: Duration,
+}
+
+impl<G: GateOut> PulseGate<G> {
+    pub(crate) fn new(gate: G, policy: OverlapPolicy) -> Self {
+        Self {
+            gate,
+            policy,
+            state: State::Low,
+            queued: 0,
+            duration: Duration::from_ticks(0),
+        }
+    }
+
+    /// Next instant at which [`PulseGate::update`] must be called.
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        match self.state {
+            State::Low => None,
+            State::High { until } | State::Gap { until } => Some(until),
+        }
+    }
+
+    pub(crate) async fn trigger(&mut self, duration: Duration) {
+        self.duration = duration;
+
+        match (&mut self.state, self.policy) {
+            (State::Low, _) => self.start().await,
+            (State::High { .. }, OverlapPolicy::Drop) => {}
+            (State::High { .. }, OverlapPolicy::Retrigger) => {
+                self.gate.set_low().await;
+                Timer::after(PULSE_GAP).await;
+                self.start().await;
+            }
+            (State::High { until }, OverlapPolicy::Extend) => {
+                *until = (*until).max(Instant::now() + duration);
+            }
+            (State::High { .. } | State::Gap { .. }, _) => {
+                self.queued = (self.queued + 1).min(MAX_QUEUED_PULSES);
+            }
+        }
+    }
+
+    pub(crate) async fn update(&mut self) {
+        let now = Instant::now();
+
+        match self.state {
+            State::High { until } if until <= now => {
+                self.gate.set_low().await;
+                self.state = if self.queued > 0 {
+                    State::Gap {
+                        until: now + PULSE_GAP,
+                    }
+                } else {
+                    State::Low
+                };
+            }
+            State::Gap { until } if until <= now => {
+                self.queued -= 1;
+                self.start().await;
+            }
+            _ => {}
+        }
+    }
+
+    /// Drives the output level directly, cancelling any pulse in progress.
+    pub(crate) async fn set_level(&mut self, high: bool) {
+        self.state = State::Low;
+        self.queued = 0;
+
+        if high {
+            self.gate.set_high().await;
+        } else {
+            self.gate.set_low().await;
+        }
+    }
+
+    async fn start(&mut self) {
+        self.gate.set_high().await;
+        self.state = State::High {
+            until: Instant::now() + self.duration,
+        };
+    }
+}
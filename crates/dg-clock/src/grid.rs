@@ -0,0 +1,95 @@
+use embassy_time::{Duration, Instant, Timer};
+
+use crate::ratchet_offset;
+
+/// Deadlines anchored to an absolute instant, typically the one returned by
+/// [`dg_types::ClockIn::wait`].
+///
+/// Every deadline is computed from the origin rather than from the previous pulse, so that the
+/// time spent emitting pulses never accumulates into drift.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PulseGrid {
+    origin: Instant,
+    period: Duration,
+    index: u32,
+    curve: Option<Curve>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Curve {
+    span: Duration,
+    count: u32,
+    curve: f32,
+}
+
+impl PulseGrid {
+    /// Grid of deadlines evenly spaced by `period`.
+    pub const fn new(origin: Instant, period: Duration) -> Self {
+        Self {
+            origin,
+            period,
+            index: 0,
+            curve: None,
+        }
+    }
+
+    /// Grid of `count` deadlines fitted into `span` and spaced according to `curve` (see
+    /// [`ratchet_offset`]). Past the `count`-th deadline, which falls at the end of `span`, the
+    /// grid carries on evenly at the mean spacing.
+    pub fn curved(origin: Instant, span: Duration, count: u32, curve: f32) -> Self {
+        let count = count.max(1);
+
+        Self {
+            origin,
+            period: span / count,
+            index: 0,
+            curve: Some(Curve { span, count, curve }),
+        }
+    }
+
+    pub const fn origin(&self) -> Instant {
+        self.origin
+    }
+
+    /// Spacing of the deadlines, or their mean spacing on a curved grid.
+    pub const fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Number of deadlines passed so far.
+    pub const fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Instant of the `index`-th deadline.
+    pub fn at(&self, index: u32) -> Instant {
+        match self.curve {
+            Some(Curve { span, count, curve }) if index < count => {
+                self.origin + ratchet_offset(span, index, count, curve)
+            }
+            Some(Curve { span, count, .. }) => self.origin + span + self.period * (index - count),
+            None => self.origin + self.period * index,
+        }
+    }
+
+    /// Instant of the next deadline.
+    pub fn deadline(&self) -> Instant {
+        self.at(self.index)
+    }
+
+    /// Moves on to the following deadline.
+    pub fn advance(&mut self) {
+        self.index = self.index.wrapping_add(1);
+    }
+
+    /// Waits for the next deadline with [`Timer::at`], moves past it and returns its instant.
+    ///
+    /// Cancel-safe: the grid only advances once the deadline is reached. If it is already past,
+    /// this returns right away, so that a late caller catches up rather than shifting the grid.
+    pub async fn wait(&mut self) -> Instant {
+        let deadline = self.deadline();
+        Timer::at(deadline).await;
+        self.advance();
+        deadline
+    }
+}
@@ -0,0 +1,162 @@
+use embassy_futures::select::{Either, select};
+use embassy_time::{Duration, Instant};
+
+use dg_noise::NoiseGenerator;
+use dg_types::{ClockIn, CvIn, CvOut, FloatParameter, GateIn};
+
+use crate::time::wait_until;
+
+/// Signal sampled by [`sample_and_hold`] and [`track_and_hold`], scaled to the whole CV range.
+///
+/// Implemented by all [`CvIn`]s, while noise generators and parameters are wrapped in
+/// [`NoiseSource`] and [`ParameterSource`].
+#[allow(async_fn_in_trait)]
+pub trait SampleSource {
+    async fn sample(&mut self) -> u16;
+}
+
+impl<C: CvIn> SampleSource for C {
+    async fn sample(&mut self) -> u16 {
+        self.get_value().await
+    }
+}
+
+/// [`SampleSource`] drawing a new noise sample every time.
+#[derive(Debug, Clone)]
+pub struct NoiseSource<N: NoiseGenerator>(pub N);
+
+impl<N: NoiseGenerator> SampleSource for NoiseSource<N> {
+    async fn sample(&mut self) -> u16 {
+        self.0.sample()
+    }
+}
+
+/// [`SampleSource`] reading a parameter in `0.0..=1.0`.
+#[derive(Debug, Clone)]
+pub struct ParameterSource<P: FloatParameter>(pub P);
+
+impl<P: FloatParameter> SampleSource for ParameterSource<P> {
+    async fn sample(&mut self) -> u16 {
+        (self.0.get().await.clamp(0.0, 1.0) * u16::MAX as f32 + 0.5) as u16
+    }
+}
+
+/// Limits how fast a CV output moves toward its target.
+#[derive(Debug, Clone, Default)]
+pub struct SlewLimiter {
+    value: f32,
+    target: f32,
+}
+
+impl SlewLimiter {
+    pub const fn new() -> Self {
+        Self {
+            value: 0.0,
+            target: 0.0,
+        }
+    }
+
+    pub fn value(&self) -> u16 {
+        (self.value + 0.5) as u16
+    }
+
+    pub fn set_target(&mut self, target: u16) {
+        self.target = target as f32;
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.value == self.target
+    }
+
+    /// Moves toward the target for `elapsed`, at a rate covering the whole CV range in `time`
+    /// milliseconds (0 jumps to the target right away).
+    pub fn advance(&mut self, elapsed: Duration, time: f32) -> u16 {
+        let step = if time > 0.0 {
+            u16::MAX as f32 * elapsed.as_micros() as f32 / (time * 1000.0)
+        } else {
+            f32::INFINITY
+        };
+
+        self.value += (self.target - self.value).clamp(-step, step);
+        self.value()
+    }
+}
+
+/// Samples `source` on every clock and holds the value on `cv_out`.
+///
+/// With a non-zero `slew` (in milliseconds for the whole CV range), the output glides to each
+/// new value, updated every `update_interval`.
+pub async fn sample_and_hold(
+    mut clock_in: impl ClockIn,
+    mut source: impl SampleSource,
+    mut cv_out: impl CvOut,
+    mut slew: impl FloatParameter,
+    update_interval: Duration,
+) {
+    let mut output = SlewLimiter::new();
+    let mut updated_at = Instant::now();
+
+    loop {
+        let deadline = (!output.is_settled()).then_some(updated_at + update_interval);
+        let event = select(clock_in.wait(), wait_until(deadline)).await;
+
+        let now = Instant::now();
+        let slew = slew.get().await;
+        output.advance(now - updated_at, slew);
+        updated_at = now;
+
+        if let Either::First(_) = event {
+            // without slew, the output jumps to the new value right away
+            output.set_target(source.sample().await);
+            output.advance(Duration::from_ticks(0), slew);
+        }
+
+        cv_out.set_value(output.value()).await;
+    }
+}
+
+/// Follows `source` while `gate_in` is high, and holds the last value while it is low.
+///
+/// The source is read every `update_interval` while tracking. With a non-zero `slew` (in
+/// milliseconds for the whole CV range), the output glides toward the source.
+pub async fn track_and_hold(
+    mut gate_in: impl GateIn,
+    mut source: impl SampleSource,
+    mut cv_out: impl CvOut,
+    mut slew: impl FloatParameter,
+    update_interval: Duration,
+) {
+    let mut output = SlewLimiter::new();
+    let mut updated_at = Instant::now();
+    let mut tracking = false;
+
+    loop {
+        let deadline = (tracking || !output.is_settled()).then_some(updated_at + update_interval);
+        let edge = async {
+            if tracking {
+                gate_in.wait_for_low().await;
+            } else {
+                gate_in.wait_for_high().await;
+            }
+        };
+
+        let event = select(edge, wait_until(deadline)).await;
+
+        let now = Instant::now();
+        let slew = slew.get().await;
+        output.advance(now - updated_at, slew);
+        updated_at = now;
+
+        if let Either::First(()) = event {
+            tracking = !tracking;
+        }
+
+        // also read on the falling edge, which is the value being held
+        if tracking || matches!(event, Either::First(())) {
+            output.set_target(source.sample().await);
+            output.advance(Duration::from_ticks(0), slew);
+        }
+
+        cv_out.set_value(output.value()).await;
+    }
+}
@@ -0,0 +1,82 @@
+use core::f32::consts::PI;
+
+use embassy_futures::join::join;
+use embassy_time::{Duration, Instant};
+use rand::Rng;
+use rand_core::RngCore;
+
+use dg_types::{ClockIn, ClockOut, FloatParameter};
+
+use crate::MAX_PENDING_PULSES;
+use crate::queue::{PulseQueue, emit_scheduled};
+
+/// Distribution of the timing offsets applied by [`humanize`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JitterDistribution {
+    /// Any offset within the maximum deviation is equally likely.
+    #[default]
+    Uniform,
+
+    /// Offsets are normally distributed with a standard deviation of a third of the maximum
+    /// deviation, and clamped to it.
+    Gaussian,
+}
+
+impl JitterDistribution {
+    /// Draws an offset in `-max_deviation..=max_deviation`.
+    pub fn sample(self, rng: &mut impl RngCore, max_deviation: f32) -> f32 {
+        let max_deviation = max_deviation.max(0.0);
+        if max_deviation == 0.0 {
+            return 0.0;
+        }
+
+        let offset = match self {
+            JitterDistribution::Uniform => (2.0 * rng.r#gen::<f32>() - 1.0) * max_deviation,
+            JitterDistribution::Gaussian => {
+                // Box-Muller transform, `u` must not be 0 for the logarithm
+                let u = 1.0 - rng.r#gen::<f32>();
+                let v = rng.r#gen::<f32>();
+                let z = libm::sqrtf(-2.0 * libm::logf(u)) * libm::cosf(2.0 * PI * v);
+                z * max_deviation / 3.0
+            }
+        };
+
+        offset.clamp(-max_deviation, max_deviation)
+    }
+}
+
+/// Re-emits each incoming pulse with a random timing offset.
+///
+/// Offsets are drawn from `distribution` and bounded by `max_deviation`, in milliseconds. Since
+/// pulses can't be emitted before they are received, they are all delayed by `max_deviation`
+/// first, so that offsets are centered around that fixed latency. Pulses are never reordered: a
+/// pulse is never scheduled before the previous one, even when the deviation changes.
+pub async fn humanize(
+    mut clock_in: impl ClockIn,
+    mut clock_out: impl ClockOut,
+    mut max_deviation: impl FloatParameter,
+    distribution: JitterDistribution,
+    mut rng: impl RngCore,
+    duration: Duration,
+) {
+    let queue = PulseQueue::<MAX_PENDING_PULSES>::new();
+
+    let input = async {
+        let mut last: Option<Instant> = None;
+
+        loop {
+            let instant = clock_in.wait().await;
+            let max_deviation = max_deviation.get().await.max(0.0);
+            let offset = distribution.sample(&mut rng, max_deviation);
+
+            let delay = Duration::from_micros(((max_deviation + offset) * 1000.0) as u64);
+            let at = last.map_or(instant + delay, |last| (instant + delay).max(last));
+            last = Some(at);
+
+            // drop the pulse if too many are pending
+            let _ = queue.try_send(at);
+        }
+    };
+
+    join(input, emit_scheduled(&queue, &mut clock_out, duration)).await;
+}
@@ -0,0 +1,243 @@
+use core::f32::consts::PI;
+
+use embassy_futures::select::{Either, Either3, select, select3};
+use embassy_time::{Duration, Instant};
+
+use dg_noise::NoiseGenerator;
+use dg_types::{ClockIn, CvOut, FloatParameter, IntParameter};
+
+use crate::{PeriodMeter, PulseGrid};
+
+/// Waveform of an [`Lfo`].
+///
+/// All shapes are unipolar, spanning `0.0..=1.0` over the whole CV output range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LfoShape {
+    /// Starts halfway, rising.
+    #[default]
+    Sine,
+
+    /// Starts at the bottom, peaks halfway through the cycle.
+    Triangle,
+
+    /// Falls from the top to the bottom over the cycle.
+    Saw,
+
+    /// Rises from the bottom to the top over the cycle.
+    Ramp,
+
+    /// High for the first half of the cycle, low for the second half.
+    Square,
+
+    /// Random level, held for the whole cycle.
+    SampleAndHold,
+}
+
+/// Noise source for an [`Lfo`] that doesn't use [`LfoShape::SampleAndHold`]. Should the shape
+/// be switched to it anyway, the level is held in the middle of the range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NoNoise;
+
+impl NoiseGenerator for NoNoise {
+    fn sample(&mut self) -> u16 {
+        0x8000
+    }
+}
+
+/// Low frequency oscillator state, advanced by fractions of a cycle.
+///
+/// The random levels of [`LfoShape::SampleAndHold`] are drawn from `noise` at the start of every
+/// cycle. Other shapes never draw from it, so they can do without one, see [`Lfo::new`].
+#[derive(Debug, Clone)]
+pub struct Lfo<N: NoiseGenerator = NoNoise> {
+    noise: N,
+    shape: LfoShape,
+
+    /// Position in the current cycle, in `0.0..1.0`.
+    phase: f32,
+
+    /// Level of the current cycle in [`LfoShape::SampleAndHold`] mode.
+    held: f32,
+}
+
+impl Lfo {
+    /// LFO without a noise source, for the shapes other than [`LfoShape::SampleAndHold`].
+    pub fn new(shape: LfoShape) -> Self {
+        Self::with_noise(shape, NoNoise)
+    }
+}
+
+impl<N: NoiseGenerator> Lfo<N> {
+    pub fn with_noise(shape: LfoShape, noise: N) -> Self {
+        let mut lfo = Self {
+            noise,
+            shape,
+            phase: 0.0,
+            held: 0.0,
+        };
+        lfo.new_cycle();
+        lfo
+    }
+
+    pub fn shape(&self) -> LfoShape {
+        self.shape
+    }
+
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        let sample = shape == LfoShape::SampleAndHold && self.shape != shape;
+        self.shape = shape;
+        if sample {
+            self.new_cycle();
+        }
+    }
+
+    pub fn phase(&self) -> f32 {
+        self.phase
+    }
+
+    /// Restarts the cycle from the beginning.
+    pub fn reset(&mut self) {
+        self.phase = 0.0;
+        self.new_cycle();
+    }
+
+    /// Moves forward by `cycles` (negative values are ignored).
+    pub fn advance(&mut self, cycles: f32) {
+        let phase = self.phase + cycles.max(0.0);
+        if phase >= 1.0 {
+            self.new_cycle();
+        }
+        self.phase = phase - libm::floorf(phase);
+    }
+
+    /// Jumps to `phase`, e.g. to follow a clock.
+    ///
+    /// Jumping back by more than half a cycle is taken as moving forward past the end of the
+    /// cycle, which starts a new one.
+    pub fn sync(&mut self, phase: f32) {
+        let phase = phase - libm::floorf(phase);
+        if self.phase - phase > 0.5 {
+            self.new_cycle();
+        }
+        self.phase = phase;
+    }
+
+    /// Current level, in `0.0..=1.0`.
+    pub fn level(&self) -> f32 {
+        let phase = self.phase;
+        match self.shape {
+            LfoShape::Sine => 0.5 + 0.5 * libm::sinf(2.0 * PI * phase),
+            LfoShape::Triangle if phase < 0.5 => 2.0 * phase,
+            LfoShape::Triangle => 2.0 - 2.0 * phase,
+            LfoShape::Saw => 1.0 - phase,
+            LfoShape::Ramp => phase,
+            LfoShape::Square if phase < 0.5 => 1.0,
+            LfoShape::Square => 0.0,
+            LfoShape::SampleAndHold => self.held,
+        }
+    }
+
+    /// Current level, scaled to the whole CV output range.
+    pub fn value(&self) -> u16 {
+        (self.level().clamp(0.0, 1.0) * u16::MAX as f32 + 0.5) as u16
+    }
+
+    fn new_cycle(&mut self) {
+        if self.shape == LfoShape::SampleAndHold {
+            self.held = self.noise.sample() as f32 / u16::MAX as f32;
+        }
+    }
+}
+
+/// Number of cycles elapsed in `elapsed` at `frequency` Hz.
+fn cycles(frequency: f32, elapsed: Duration) -> f32 {
+    frequency * elapsed.as_micros() as f32 / 1_000_000.0
+}
+
+/// Free-running LFO at the rate read from `rate`, in Hz.
+///
+/// `cv_out` is updated every `update_interval`, and an edge on `reset` restarts the cycle.
+pub async fn lfo(
+    mut reset: impl ClockIn,
+    mut cv_out: impl CvOut,
+    mut lfo: Lfo<impl NoiseGenerator>,
+    mut rate: impl FloatParameter,
+    update_interval: Duration,
+) {
+    let mut grid = PulseGrid::new(Instant::now(), update_interval);
+    let mut updated_at = grid.origin();
+
+    loop {
+        let now = match select(reset.wait(), grid.wait()).await {
+            Either::First(now) => {
+                lfo.reset();
+                now
+            }
+            Either::Second(now) => {
+                let elapsed = now.saturating_duration_since(updated_at);
+                lfo.advance(cycles(rate.get().await, elapsed));
+                now
+            }
+        };
+
+        // a grid deadline may be before the edge just handled
+        updated_at = updated_at.max(now);
+        cv_out.set_value(lfo.value()).await;
+    }
+}
+
+/// LFO synced to `clock_in`, running `multiply` cycles every `divide` clocks.
+///
+/// The rate follows the measured clock period, and the phase is re-aligned on every clock, so
+/// that the LFO stays locked to the clock when its tempo drifts. Until the period is known, the
+/// LFO only moves on clocks. An edge on `reset` restarts the cycle and the clock count.
+pub async fn lfo_synced(
+    mut clock_in: impl ClockIn,
+    mut reset: impl ClockIn,
+    mut cv_out: impl CvOut,
+    mut lfo: Lfo<impl NoiseGenerator>,
+    mut multiply: impl IntParameter,
+    mut divide: impl IntParameter,
+    update_interval: Duration,
+) {
+    let mut grid = PulseGrid::new(Instant::now(), update_interval);
+    let mut updated_at = grid.origin();
+    let mut meter = PeriodMeter::new();
+
+    // clocks since the start, modulo the divide ratio
+    let mut clocks: u32 = 0;
+
+    loop {
+        let now = match select3(clock_in.wait(), reset.wait(), grid.wait()).await {
+            Either3::First(now) => {
+                meter.tick(now);
+
+                let multiply = multiply.get().await.max(1) as u32;
+                let divide = divide.get().await.max(1) as u32;
+                clocks %= divide;
+                let position = clocks as u64 * multiply as u64 % divide as u64;
+                lfo.sync(position as f32 / divide as f32);
+                clocks += 1;
+                now
+            }
+            Either3::Second(now) => {
+                lfo.reset();
+                clocks = 0;
+                now
+            }
+            Either3::Third(now) => {
+                if let Some(period) = meter.period() {
+                    let ratio =
+                        multiply.get().await.max(1) as f32 / divide.get().await.max(1) as f32;
+                    let frequency = ratio * 1_000_000.0 / period.as_micros().max(1) as f32;
+                    lfo.advance(cycles(frequency, now.saturating_duration_since(updated_at)));
+                }
+                now
+            }
+        };
+
+        // a grid deadline may be before the edge just handled
+        updated_at = updated_at.max(now);
+        cv_out.set_value(lfo.value()).await;
+    }
+}
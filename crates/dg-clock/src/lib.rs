@@ -1,75 +1,90 @@
 #![no_std]
 
-use dg_types::{ClockIn, ClockOut, FloatParameter, IntParameter};
-use embassy_time::{Duration, Ticker, Timer};
+mod bernoulli;
+mod bus;
+mod clock;
+mod convert;
+mod delay;
+mod dust;
+mod envelope;
+mod gate;
+mod grid;
+mod hold;
+mod humanize;
+mod lfo;
+mod logic;
+mod midi;
+mod monitor;
+mod period;
+mod polyrhythm;
+mod position;
+mod queue;
+mod scheduler;
+mod sequencer;
+mod staircase;
+mod swing;
+mod time;
+mod train;
+mod transport;
+mod turing;
+
+pub use self::{
+    bernoulli::{BernoulliGate, BernoulliMode, BernoulliOutput, bernoulli_gate},
+    bus::{ClockBus, ClockSubscriber},
+    clock::{InternalClock, SyncMode, clock, clock_synced},
+    convert::{GateEdge, GateLengthMode, gate_to_trigger, trigger_to_gate},
+    delay::{DelayMode, MAX_PENDING_PULSES, clock_delay},
+    dust::{Dust, dust},
+    envelope::{Adsr, Envelope, EnvelopeCurve, EnvelopeMode, clock_envelope, envelope},
+    gate::{MAX_QUEUED_PULSES, OverlapPolicy},
+    grid::PulseGrid,
+    hold::{
+        NoiseSource, ParameterSource, SampleSource, SlewLimiter, sample_and_hold, track_and_hold,
+    },
+    humanize::{JitterDistribution, humanize},
+    lfo::{Lfo, LfoShape, NoNoise, lfo, lfo_synced},
+    logic::{LogicOp, LogicOutput, clock_logic},
+    midi::{MIDI_PPQN, MidiClockEvent, MidiClockIn, MidiClockOut, MidiClockParser, clock_to_midi},
+    monitor::{ClockEvent, ClockMonitor, FallbackClock},
+    period::PeriodMeter,
+    polyrhythm::polyrhythm,
+    position::{BarPosition, PositionTracker, TimeSignature, bar_clock},
+    scheduler::{EventScheduler, OutputAction, ScheduledEvent, SchedulerFull},
+    sequencer::{MAX_RATCHETS, MAX_STEPS, Pattern, Step, TrigCondition, step_sequencer},
+    staircase::{Staircase, StaircaseDirection, staircase},
+    swing::{MAX_SWING_AMOUNT, SwingGrid, swing},
+    train::{BurstPolicy, TrainMode, clock_train, ratchet_offset},
+    transport::{Transport, TransportState, transport_button, transport_gate},
+    turing::turing_machine,
+};
+
+use embassy_futures::join::join;
+use embassy_time::Duration;
+
+use dg_types::{ClockIn, ClockOut};
+
+use crate::queue::{TriggerQueue, emit_triggered};
 
 /// Simple clock forwarder
 ///
 /// With the patch.Init(), this can be useful to wire the b7 push button to one of the output. This
 /// way, pulses can be triggered manually for testing purposes.
+///
+/// `policy` decides what happens when a clock arrives while the previous pulse is still high.
 pub async fn clock_forward(
     mut clock_in: impl ClockIn,
     mut clock_out: impl ClockOut,
     duration: Duration,
+    policy: OverlapPolicy,
 ) {
-    loop {
-        clock_in.wait().await;
-        clock_out.emit_pulse(duration).await;
-    }
-}
-
-/// Emits a train of pulse for each incoming clock signal.
-pub async fn clock_train(
-    mut clock_in: impl ClockIn,
-    mut clock_out: impl ClockOut,
-    mut pulse_count: impl IntParameter,
-    mut pulse_bpm: impl FloatParameter,
-) {
-    loop {
-        clock_in.wait().await;
-
-        let count = pulse_count.get().await;
-        let pulse_period_us = ((60.0 * 1_000_000.0) / pulse_bpm.get().await) as u64;
-
-        let pulse_width_us = 10_000.min(pulse_period_us / 2);
-        let pulse_width = Duration::from_micros(pulse_width_us);
-        let rest_width = Duration::from_micros(pulse_period_us - pulse_width_us);
-
-        for _ in 0..count {
-            clock_out.emit_pulse(pulse_width).await;
-            Timer::after(rest_width).await;
-        }
-    }
-}
-
-pub async fn clock(mut clock_out: impl ClockOut, mut pulse_pbm: impl FloatParameter) {
-    let mut ticker = VaryingTicker::default();
-
-    loop {
-        ticker.next(pulse_pbm.get().await).await;
-        clock_out.emit_pulse(Duration::from_millis(5)).await;
-    }
-}
-
-#[derive(Default)]
-struct VaryingTicker {
-    ticker: Option<Ticker>,
-    current_bpm: Option<f32>,
-}
+    let triggers = TriggerQueue::new();
 
-impl VaryingTicker {
-    pub async fn next(&mut self, bpm: f32) {
-        // invalidate ticker if bpm changed
-        if self.current_bpm != Some(bpm) {
-            self.ticker = None;
-            self.current_bpm = Some(bpm);
+    let input = async {
+        loop {
+            clock_in.wait().await;
+            let _ = triggers.try_send(duration);
         }
+    };
 
-        self.ticker
-            .get_or_insert_with(|| {
-                Ticker::every(Duration::from_micros(((60.0 * 1_000_000.0) / bpm) as u64))
-            })
-            .next()
-            .await;
-    }
+    join(input, emit_triggered(&triggers, &mut clock_out, policy)).await;
 }
@@ -0,0 +1,164 @@
+use embassy_futures::join::{join, join_array};
+use embassy_futures::select::{Either3, select3};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, channel::Channel};
+use embassy_time::{Duration, Instant};
+
+use dg_types::{ClockIn, GateOut};
+
+use crate::OverlapPolicy;
+use crate::gate::PulseGate;
+use crate::time::wait_until;
+
+/// Edges received but not yet processed by [`clock_logic`].
+const MAX_PENDING_EDGES: usize = 8;
+
+/// Edges tagged with the index of their input.
+type EdgeQueue = Channel<NoopRawMutex, (Instant, usize), MAX_PENDING_EDGES>;
+
+/// Operation combining the edges of the inputs of [`clock_logic`].
+///
+/// Edges falling within the same coincidence window are considered simultaneous. Each window is
+/// then evaluated once, based on which inputs fired during it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogicOp {
+    /// All inputs fired.
+    And,
+
+    /// At least one input fired.
+    Or,
+
+    /// An odd number of inputs fired (with two inputs: exactly one of them).
+    Xor,
+
+    /// Some inputs fired, but not all of them.
+    Nand,
+
+    /// Toggle flip-flop: the output changes state on every window (a divide-by-two for a single
+    /// input).
+    Toggle,
+
+    /// Set/reset latch: the first input sets the output, the second one resets it. Reset wins
+    /// when both coincide. Further inputs are ignored.
+    Latch,
+}
+
+impl LogicOp {
+    /// Output level after a window during which the inputs in `fired` had an edge.
+    fn eval(self, fired: &[bool], level: bool) -> bool {
+        let count = fired.iter().filter(|f| **f).count();
+        let input = |index: usize| fired.get(index).copied().unwrap_or(false);
+
+        match self {
+            LogicOp::And => count == fired.len(),
+            LogicOp::Or => count > 0,
+            LogicOp::Xor => count % 2 == 1,
+            LogicOp::Nand => count < fired.len(),
+            LogicOp::Toggle => !level,
+            LogicOp::Latch => (level || input(0)) && !input(1),
+        }
+    }
+
+    /// Whether the output is a level held across windows (flip-flops), or a per-window result.
+    fn is_stateful(self) -> bool {
+        matches!(self, LogicOp::Toggle | LogicOp::Latch)
+    }
+}
+
+/// Signal produced by [`clock_logic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogicOutput {
+    /// A pulse of the given duration whenever a window evaluates true, or, for flip-flops,
+    /// whenever the output goes high.
+    Trigger(Duration),
+
+    /// The output level is held until the next window changes it.
+    Gate,
+}
+
+/// Combines several clock inputs with a boolean operation or flip-flop.
+///
+/// An edge opens a coincidence window of length `window`, and every edge received until it closes
+/// counts as simultaneous. The window closes early once all inputs have fired, otherwise the
+/// result is delayed by `window`. Gate inputs can be used as well, in which case their rising
+/// edges are considered.
+pub async fn clock_logic<C: ClockIn, const N: usize>(
+    mut inputs: [C; N],
+    gate_out: impl GateOut,
+    op: LogicOp,
+    window: Duration,
+    output: LogicOutput,
+) {
+    // each input is listened to continuously, so that edges arriving together are all caught
+    let edges = EdgeQueue::new();
+    let mut index = 0;
+    let listeners = inputs.each_mut().map(|input| {
+        index += 1;
+        listen(input, index - 1, &edges)
+    });
+
+    join(
+        join_array(listeners),
+        combine::<N>(&edges, gate_out, op, window, output),
+    )
+    .await;
+}
+
+async fn listen(input: &mut impl ClockIn, index: usize, edges: &EdgeQueue) {
+    loop {
+        let at = input.wait().await;
+        let _ = edges.try_send((at, index));
+    }
+}
+
+async fn combine<const N: usize>(
+    edges: &EdgeQueue,
+    gate_out: impl GateOut,
+    op: LogicOp,
+    window: Duration,
+    output: LogicOutput,
+) {
+    let mut gate = PulseGate::new(gate_out, OverlapPolicy::Drop);
+    let mut level = false;
+    let mut fired = [false; N];
+    let mut window_end: Option<Instant> = None;
+
+    loop {
+        match select3(
+            edges.receive(),
+            wait_until(window_end),
+            wait_until(gate.deadline()),
+        )
+        .await
+        {
+            Either3::First((at, index)) => {
+                fired[index] = true;
+                let end = *window_end.get_or_insert(at + window);
+
+                // nothing left to wait for
+                if fired.iter().all(|f| *f) {
+                    window_end = Some(end.min(Instant::now()));
+                }
+            }
+            Either3::Second(()) => {
+                window_end = None;
+                let result = op.eval(&fired, level);
+                fired = [false; N];
+
+                let fire = if op.is_stateful() {
+                    result && !level
+                } else {
+                    result
+                };
+
+                match output {
+                    LogicOutput::Trigger(duration) if fire => gate.trigger(duration).await,
+                    LogicOutput::Gate if result != level => gate.set_level(result).await,
+                    _ => {}
+                }
+
+                level = result;
+            }
+            Either3::Third(()) => gate.update().await,
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use embassy_futures::select::{Either, select};
+use embassy_time::{Duration, Instant};
+
+use dg_types::{ByteIn, ByteOut, ClockIn, ClockOut};
+
+use crate::time::wait_until;
+use crate::{PeriodMeter, PulseGrid};
+
+/// MIDI clocks per quarter note.
+pub const MIDI_PPQN: u32 = 24;
+
+/// MIDI clocks per song position unit (a sixteenth note).
+const CLOCKS_PER_SONG_POSITION: u32 = 6;
+
+const TIMING_CLOCK: u8 = 0xF8;
+const START: u8 = 0xFA;
+const CONTINUE: u8 = 0xFB;
+const STOP: u8 = 0xFC;
+const SONG_POSITION: u8 = 0xF2;
+
+/// Clock-related MIDI message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiClockEvent {
+    /// Timing clock, sent 24 times per quarter note.
+    Clock,
+    Start,
+    Continue,
+    Stop,
+
+    /// Song position, in sixteenth notes since the start of the song.
+    SongPosition(u16),
+}
+
+impl MidiClockEvent {
+    /// Bytes encoding the message.
+    pub fn encode(self) -> ([u8; 3], usize) {
+        match self {
+            MidiClockEvent::Clock => ([TIMING_CLOCK, 0, 0], 1),
+            MidiClockEvent::Start => ([START, 0, 0], 1),
+            MidiClockEvent::Continue => ([CONTINUE, 0, 0], 1),
+            MidiClockEvent::Stop => ([STOP, 0, 0], 1),
+            MidiClockEvent::SongPosition(position) => (
+                [
+                    SONG_POSITION,
+                    (position & 0x7F) as u8,
+                    ((position >> 7) & 0x7F) as u8,
+                ],
+                3,
+            ),
+        }
+    }
+}
+
+/// Extracts [`MidiClockEvent`]s from a MIDI byte stream, ignoring all other messages.
+///
+/// Real-time bytes may be interleaved within other messages, as allowed by the MIDI spec.
+#[derive(Debug, Clone, Default)]
+pub struct MidiClockParser {
+    /// Set while a song position message is being parsed, with its LSB once received.
+    song_position: Option<Option<u8>>,
+}
+
+impl MidiClockParser {
+    pub const fn new() -> Self {
+        Self {
+            song_position: None,
+        }
+    }
+
+    pub fn push(&mut self, byte: u8) -> Option<MidiClockEvent> {
+        match byte {
+            TIMING_CLOCK => Some(MidiClockEvent::Clock),
+            START => Some(MidiClockEvent::Start),
+            CONTINUE => Some(MidiClockEvent::Continue),
+            STOP => Some(MidiClockEvent::Stop),
+
+            // other real-time messages don't interrupt the message being parsed
+            0xF9..=0xFF => None,
+
+            SONG_POSITION => {
+                self.song_position = Some(None);
+                None
+            }
+
+            // any other status byte ends the song position message
+            0x80..=0xF7 => {
+                self.song_position = None;
+                None
+            }
+
+            data => match self.song_position {
+                Some(None) => {
+                    self.song_position = Some(Some(data));
+                    None
+                }
+                Some(Some(lsb)) => {
+                    self.song_position = None;
+                    Some(MidiClockEvent::SongPosition(
+                        ((data as u16) << 7) | lsb as u16,
+                    ))
+                }
+                None => None,
+            },
+        }
+    }
+}
+
+/// [`ClockIn`] following the MIDI clock received on a byte stream.
+///
+/// A pulse is produced every `division` MIDI clocks while the transport is running (e.g.
+/// [`MIDI_PPQN`] for quarter notes, 6 for sixteenths), counted from the last start or song
+/// position. Clocks received while stopped are ignored.
+pub struct MidiClockIn<B: ByteIn> {
+    bytes: B,
+    parser: MidiClockParser,
+    division: u32,
+    running: bool,
+
+    /// Number of MIDI clocks since the start of the song.
+    position: u32,
+}
+
+impl<B: ByteIn> MidiClockIn<B> {
+    pub fn new(bytes: B, division: u32) -> Self {
+        Self {
+            bytes,
+            parser: MidiClockParser::new(),
+            division: division.max(1),
+            running: false,
+            position: 0,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Number of MIDI clocks since the start of the song.
+    pub fn position(&self) -> u32 {
+        self.position
+    }
+
+    /// Waits for the next clock-related message and updates the transport state and position
+    /// accordingly.
+    ///
+    /// Returns the event and the instant at which it was received.
+    pub async fn next_event(&mut self) -> (MidiClockEvent, Instant) {
+        loop {
+            let byte = self.bytes.read_byte().await;
+            let Some(event) = self.parser.push(byte) else {
+                continue;
+            };
+
+            let now = Instant::now();
+            match event {
+                MidiClockEvent::Start => {
+                    self.running = true;
+                    self.position = 0;
+                }
+                MidiClockEvent::Continue => self.running = true,
+                MidiClockEvent::Stop => self.running = false,
+                MidiClockEvent::SongPosition(position) => {
+                    self.position = position as u32 * CLOCKS_PER_SONG_POSITION;
+                }
+                MidiClockEvent::Clock if self.running => {
+                    self.position = self.position.wrapping_add(1);
+                }
+                MidiClockEvent::Clock => {}
+            }
+
+            return (event, now);
+        }
+    }
+}
+
+impl<B: ByteIn> ClockIn for MidiClockIn<B> {
+    async fn wait(&mut self) -> Instant {
+        loop {
+            let (event, now) = self.next_event().await;
+
+            // `position` was just advanced past this clock
+            if event == MidiClockEvent::Clock
+                && self.running
+                && self.position.wrapping_sub(1) % self.division == 0
+            {
+                return now;
+            }
+        }
+    }
+}
+
+/// [`ClockOut`] sending a MIDI timing clock for every pulse.
+///
+/// Used as a [`ClockOut`], the pulse stream must already run at [`MIDI_PPQN`] pulses per quarter
+/// note: use [`clock_to_midi`] for clocks at other resolutions. Transport messages are sent with
+/// the dedicated methods.
+pub struct MidiClockOut<B: ByteOut> {
+    bytes: B,
+}
+
+impl<B: ByteOut> MidiClockOut<B> {
+    pub fn new(bytes: B) -> Self {
+        Self { bytes }
+    }
+
+    pub async fn send(&mut self, event: MidiClockEvent) {
+        let (bytes, len) = event.encode();
+        for byte in &bytes[..len] {
+            self.bytes.write_byte(*byte).await;
+        }
+    }
+
+    pub async fn start(&mut self) {
+        self.send(MidiClockEvent::Start).await;
+    }
+
+    pub async fn resume(&mut self) {
+        self.send(MidiClockEvent::Continue).await;
+    }
+
+    pub async fn stop(&mut self) {
+        self.send(MidiClockEvent::Stop).await;
+    }
+
+    /// Moves the receivers to `position`, in sixteenth notes (only takes effect while stopped).
+    pub async fn song_position(&mut self, position: u16) {
+        self.send(MidiClockEvent::SongPosition(position & 0x3FFF))
+            .await;
+    }
+}
+
+impl<B: ByteOut> ClockOut for MidiClockOut<B> {
+    /// The duration is irrelevant, MIDI clocks having no length.
+    async fn emit_pulse(&mut self, _duration: Duration) {
+        self.send(MidiClockEvent::Clock).await;
+    }
+}
+
+/// Number of MIDI clocks due for pulse `index` (`0..ppqn`) of a quarter note at `ppqn` pulses per
+/// quarter note.
+fn clocks_per_pulse(index: u32, ppqn: u32) -> u32 {
+    ((index + 1) * MIDI_PPQN).div_ceil(ppqn) - (index * MIDI_PPQN).div_ceil(ppqn)
+}
+
+/// Sends MIDI timing clocks following `clock_in`, which runs at `ppqn` pulses per quarter note.
+///
+/// [`MIDI_PPQN`] clocks are sent per quarter note: when `ppqn` is lower, the clocks of a pulse
+/// are spread over the measured period, and when it is higher, some pulses send no clock (e.g.
+/// every other pulse at 48 PPQN). The clocks of a pulse which are still due when the next pulse
+/// arrives, e.g. when the tempo increases or before the period is known, are sent right away, so
+/// that receivers keep counting the song position correctly.
+pub async fn clock_to_midi<B: ByteOut>(
+    mut clock_in: impl ClockIn,
+    midi_out: &mut MidiClockOut<B>,
+    ppqn: u32,
+) {
+    let ppqn = ppqn.max(1);
+    let mut meter = PeriodMeter::new();
+    let mut pulse_index = 0;
+
+    // clocks of the current pulse still to be sent, on a grid once the period is known
+    let mut grid: Option<PulseGrid> = None;
+    let mut due = 0;
+
+    loop {
+        let deadline = grid.filter(|_| due > 0).map(|grid| grid.deadline());
+
+        match select(clock_in.wait(), wait_until(deadline)).await {
+            Either::First(instant) => {
+                let period = meter.tick(instant);
+
+                // clocks of the previous pulse still due are sent right away
+                for _ in 0..due {
+                    midi_out.send(MidiClockEvent::Clock).await;
+                }
+
+                due = clocks_per_pulse(pulse_index, ppqn);
+                pulse_index = (pulse_index + 1) % ppqn;
+                if due == 0 {
+                    grid = None;
+                    continue;
+                }
+
+                // the first clock of the pulse is sent right away, the others on the grid
+                grid = period.map(|period| PulseGrid::new(instant, period / due));
+            }
+            Either::Second(()) => {}
+        }
+
+        midi_out.send(MidiClockEvent::Clock).await;
+        due -= 1;
+        if let Some(grid) = grid.as_mut() {
+            grid.advance();
+        }
+    }
+}
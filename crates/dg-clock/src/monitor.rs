@@ -0,0 +1,120 @@
+use embassy_futures::select::{Either, select};
+use embassy_time::{Duration, Instant};
+
+use dg_types::{ClockIn, FloatParameter};
+
+use crate::InternalClock;
+use crate::time::wait_until;
+
+/// Event reported by a [`ClockMonitor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockEvent {
+    /// A pulse of a clock which is present.
+    Pulse(Instant),
+
+    /// The first pulse since the monitor started or the clock was lost.
+    ClockPresent(Instant),
+
+    /// No pulse was received for the whole timeout, the instant is when it expired.
+    ClockLost(Instant),
+}
+
+/// Watches a [`ClockIn`] and reports when it appears and disappears.
+///
+/// The clock is considered lost when no pulse arrives for `timeout` after the previous one,
+/// which should be longer than the slowest expected period. The monitor is itself a [`ClockIn`],
+/// yielding the pulses of the monitored clock.
+pub struct ClockMonitor<C: ClockIn> {
+    clock_in: C,
+    timeout: Duration,
+
+    /// Last pulse received, while the clock is present.
+    last: Option<Instant>,
+}
+
+impl<C: ClockIn> ClockMonitor<C> {
+    /// Creates a monitor, considering the clock lost until its first pulse.
+    pub fn new(clock_in: C, timeout: Duration) -> Self {
+        Self {
+            clock_in,
+            timeout,
+            last: None,
+        }
+    }
+
+    pub fn is_present(&self) -> bool {
+        self.last.is_some()
+    }
+
+    /// Waits for the next pulse or change of presence.
+    pub async fn next_event(&mut self) -> ClockEvent {
+        let deadline = self.last.map(|last| last + self.timeout);
+
+        match select(self.clock_in.wait(), wait_until(deadline)).await {
+            Either::First(instant) => match self.last.replace(instant) {
+                Some(_) => ClockEvent::Pulse(instant),
+                None => ClockEvent::ClockPresent(instant),
+            },
+            Either::Second(()) => {
+                self.last = None;
+                ClockEvent::ClockLost(deadline.unwrap_or_else(Instant::now))
+            }
+        }
+    }
+}
+
+impl<C: ClockIn> ClockIn for ClockMonitor<C> {
+    async fn wait(&mut self) -> Instant {
+        loop {
+            match self.next_event().await {
+                ClockEvent::Pulse(instant) | ClockEvent::ClockPresent(instant) => return instant,
+                ClockEvent::ClockLost(_) => {}
+            }
+        }
+    }
+}
+
+/// [`ClockIn`] following an external clock while it is present, and falling back to an internal
+/// clock when it is lost.
+///
+/// The internal clock runs at the tempo of its own BPM parameter, in phase with the last external
+/// pulse: the ticks it would have emitted before the loss was detected are skipped. The external
+/// clock takes over again as soon as it comes back.
+pub struct FallbackClock<C: ClockIn, P: FloatParameter> {
+    external: ClockMonitor<C>,
+    internal: InternalClock<P>,
+}
+
+impl<C: ClockIn, P: FloatParameter> FallbackClock<C, P> {
+    pub fn new(external: ClockMonitor<C>, internal: InternalClock<P>) -> Self {
+        Self { external, internal }
+    }
+
+    /// Whether pulses currently come from the external clock.
+    pub fn is_external(&self) -> bool {
+        self.external.is_present()
+    }
+}
+
+impl<C: ClockIn, P: FloatParameter> ClockIn for FallbackClock<C, P> {
+    async fn wait(&mut self) -> Instant {
+        loop {
+            let event = if self.external.is_present() {
+                self.external.next_event().await
+            } else {
+                match select(self.external.next_event(), self.internal.wait()).await {
+                    Either::First(event) => event,
+                    Either::Second(instant) => return instant,
+                }
+            };
+
+            match event {
+                ClockEvent::Pulse(instant) | ClockEvent::ClockPresent(instant) => {
+                    self.internal.align(instant);
+                    return instant;
+                }
+                ClockEvent::ClockLost(instant) => self.internal.skip_missed(instant).await,
+            }
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use embassy_time::{Duration, Instant};
+
+/// Measures the period of an incoming clock.
+///
+/// Feed it the [`Instant`] returned by each [`dg_types::ClockIn::wait`] call. The period is the
+/// time elapsed between the last two edges, so it is only known after the second edge.
+#[derive(Debug, Default, Clone)]
+pub struct PeriodMeter {
+    last: Option<Instant>,
+    period: Option<Duration>,
+}
+
+impl PeriodMeter {
+    pub const fn new() -> Self {
+        Self {
+            last: None,
+            period: None,
+        }
+    }
+
+    /// Records a clock edge and returns the updated period, if known.
+    pub fn tick(&mut self, instant: Instant) -> Option<Duration> {
+        if let Some(last) = self.last {
+            self.period = Some(instant.saturating_duration_since(last));
+        }
+        self.last = Some(instant);
+        self.period
+    }
+
+    /// Last measured period.
+    pub fn period(&self) -> Option<Duration> {
+        self.period
+    }
+
+    /// Instant of the last recorded edge.
+    pub fn last(&self) -> Option<Instant> {
+        self.last
+    }
+
+    /// Forget everything measured so far.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
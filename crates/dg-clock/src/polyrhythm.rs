@@ -0,0 +1,150 @@
+use embassy_futures::select::{Either3, select3};
+use embassy_time::{Duration, Instant};
+
+use dg_types::{ClockIn, GateOut};
+
+use crate::PeriodMeter;
+use crate::time::wait_until;
+
+struct Voice {
+    /// Pulses per cycle.
+    ratio: u32,
+
+    /// Index of the next pulse in the current cycle.
+    next: u32,
+
+    high_until: Option<Instant>,
+}
+
+struct Cycle {
+    start: Instant,
+    length: Option<Duration>,
+}
+
+impl Cycle {
+    fn pulse_time(&self, index: u32, ratio: u32) -> Option<Instant> {
+        if index == 0 {
+            return Some(self.start);
+        }
+
+        let length = self.length?;
+        Some(self.start + Duration::from_micros(length.as_micros() * index as u64 / ratio as u64))
+    }
+}
+
+impl Voice {
+    fn next_pulse(&self, cycle: &Option<Cycle>) -> Option<Instant> {
+        if self.next >= self.ratio {
+            return None;
+        }
+
+        cycle.as_ref()?.pulse_time(self.next, self.ratio)
+    }
+
+    fn deadline(&self, cycle: &Option<Cycle>) -> Option<Instant> {
+        match (self.high_until, self.next_pulse(cycle)) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+/// Polyrhythm generator.
+///
+/// Each cycle spans `cycle_beats` pulses of the `master` clock, and output `i` emits `ratios[i]`
+/// evenly spaced pulses per cycle. All outputs pulse together at the start of each cycle, and
+/// their phase is re-aligned on every master pulse. Pulses that should have been emitted before
+/// the latest master pulse (e.g. after a tempo jump) are skipped.
+///
+/// An edge on `reset` stops all outputs until the next master pulse, which then starts a new
+/// cycle. Use [`crate::InternalClock`] as `master` to run from an internal tempo.
+pub async fn polyrhythm<G: GateOut, const N: usize>(
+    mut master: impl ClockIn,
+    mut reset: impl ClockIn,
+    mut outputs: [G; N],
+    ratios: [u32; N],
+    cycle_beats: u32,
+    duration: Duration,
+) {
+    let cycle_beats = cycle_beats.max(1);
+    let mut voices = ratios.map(|ratio| Voice {
+        ratio,
+        next: ratio,
+        high_until: None,
+    });
+
+    let mut meter = PeriodMeter::new();
+    let mut cycle: Option<Cycle> = None;
+    let mut beat = 0;
+
+    loop {
+        let deadline = voices
+            .iter()
+            .filter_map(|voice| voice.deadline(&cycle))
+            .min();
+
+        match select3(master.wait(), reset.wait(), wait_until(deadline)).await {
+            Either3::First(instant) => {
+                let period = meter.tick(instant);
+                let length = period.map(|period| period * cycle_beats);
+
+                if beat == 0 {
+                    for voice in &mut voices {
+                        voice.next = 0;
+                    }
+                    cycle = Some(Cycle {
+                        start: instant,
+                        length,
+                    });
+                } else if let Some(period) = period {
+                    cycle = Some(Cycle {
+                        start: instant - period * beat,
+                        length,
+                    });
+
+                    for voice in &mut voices {
+                        while voice.next_pulse(&cycle).is_some_and(|time| time < instant) {
+                            voice.next += 1;
+                        }
+                    }
+                }
+
+                beat = (beat + 1) % cycle_beats;
+            }
+
+            Either3::Second(_) => {
+                beat = 0;
+                cycle = None;
+                for voice in &mut voices {
+                    voice.next = voice.ratio;
+                }
+            }
+
+            Either3::Third(()) => {
+                let now = Instant::now();
+
+                for (voice, output) in voices.iter_mut().zip(outputs.iter_mut()) {
+                    if voice.high_until.is_some_and(|until| until <= now) {
+                        output.set_low().await;
+                        voice.high_until = None;
+                    }
+
+                    if voice.next_pulse(&cycle).is_some_and(|time| time <= now) {
+                        if voice.high_until.is_some() {
+                            output.set_low().await;
+                        }
+
+                        let width = match cycle.as_ref().and_then(|cycle| cycle.length) {
+                            Some(length) => duration.min(length / voice.ratio / 2),
+                            None => duration,
+                        };
+
+                        output.set_high().await;
+                        voice.high_until = Some(now + width);
+                        voice.next += 1;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use core::fmt;
+
+use embassy_futures::select::{Either3, select3};
+use embassy_time::Duration;
+
+use dg_types::{ClockIn, GateOut, IntParameter};
+
+use crate::OverlapPolicy;
+use crate::gate::PulseGate;
+use crate::time::wait_until;
+
+/// Time signature, e.g. `4/4` or `7/8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeSignature {
+    /// Beats per bar.
+    pub beats: u32,
+
+    /// Note value of a beat: 4 for quarter notes, 8 for eighths...
+    pub unit: u32,
+}
+
+impl TimeSignature {
+    pub const COMMON: TimeSignature = TimeSignature::new(4, 4);
+
+    pub const fn new(beats: u32, unit: u32) -> Self {
+        Self { beats, unit }
+    }
+
+    /// Number of pulses per beat, for a clock running at `ppqn` pulses per quarter note.
+    ///
+    /// The division truncates: when the unit doesn't divide `4 * ppqn` (e.g. a 7/7 bar at 24
+    /// PPQN, 13.7 pulses per beat), each beat is rounded down to a whole number of pulses, so bars
+    /// are slightly shorter than their nominal length. Beats shorter than a pulse last a single
+    /// pulse.
+    pub const fn pulses_per_beat(self, ppqn: u32) -> u32 {
+        let pulses = ppqn.saturating_mul(4) / if self.unit == 0 { 4 } else { self.unit };
+        if pulses == 0 { 1 } else { pulses }
+    }
+}
+
+impl Default for TimeSignature {
+    fn default() -> Self {
+        Self::COMMON
+    }
+}
+
+/// Musical position of a clock pulse, all counted from zero.
+///
+/// It is displayed in the usual `bar:beat:tick` form, where bars and beats start at one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BarPosition {
+    pub bar: u32,
+    pub beat: u32,
+
+    /// Pulse within the beat.
+    pub tick: u32,
+}
+
+impl BarPosition {
+    /// Whether the pulse starts a beat.
+    pub fn is_beat(&self) -> bool {
+        self.tick == 0
+    }
+
+    /// Whether the pulse is the first one of a bar.
+    pub fn is_downbeat(&self) -> bool {
+        self.is_beat() && self.beat == 0
+    }
+
+    /// Whether the pulse is the first one of a group of `bars` bars, e.g. every 4 bars.
+    pub fn is_every_bars(&self, bars: u32) -> bool {
+        self.is_downbeat() && self.bar % bars.max(1) == 0
+    }
+}
+
+impl fmt::Display for BarPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.bar + 1, self.beat + 1, self.tick)
+    }
+}
+
+/// Tracks the bar:beat:tick position of a clock running at `ppqn` pulses per quarter note.
+#[derive(Debug, Clone)]
+pub struct PositionTracker {
+    ppqn: u32,
+    signature: TimeSignature,
+
+    /// Position of the last pulse, `None` until the first pulse after a reset.
+    position: Option<BarPosition>,
+}
+
+impl PositionTracker {
+    pub const fn new(ppqn: u32, signature: TimeSignature) -> Self {
+        Self {
+            ppqn,
+            signature,
+            position: None,
+        }
+    }
+
+    pub fn ppqn(&self) -> u32 {
+        self.ppqn
+    }
+
+    pub fn signature(&self) -> TimeSignature {
+        self.signature
+    }
+
+    /// Changes the time signature. A current bar longer than the new signature ends on the next
+    /// beat.
+    pub fn set_signature(&mut self, signature: TimeSignature) {
+        self.signature = signature;
+    }
+
+    /// Position of the last pulse, if any pulse was received since the last reset.
+    pub fn position(&self) -> Option<BarPosition> {
+        self.position
+    }
+
+    /// Makes the next pulse the first one of the first bar.
+    pub fn reset(&mut self) {
+        self.position = None;
+    }
+
+    /// Moves to the next pulse and returns its position.
+    pub fn tick(&mut self) -> BarPosition {
+        let next = match self.position {
+            None => BarPosition::default(),
+            Some(mut position) => {
+                position.tick += 1;
+                if position.tick >= self.signature.pulses_per_beat(self.ppqn) {
+                    position.tick = 0;
+                    position.beat += 1;
+                    if position.beat >= self.signature.beats.max(1) {
+                        position.beat = 0;
+                        position.bar = position.bar.wrapping_add(1);
+                    }
+                }
+                position
+            }
+        };
+
+        self.position = Some(next);
+        next
+    }
+}
+
+/// Emits a trigger of `duration` on the first pulse of every `bars` bars (read on every bar).
+///
+/// `tracker` follows the position of `clock_in`, and an edge on `reset` makes the next clock the
+/// first pulse of the first bar. `policy` decides what happens when a bar starts while the
+/// previous trigger is still high.
+pub async fn bar_clock(
+    mut clock_in: impl ClockIn,
+    mut reset: impl ClockIn,
+    gate_out: impl GateOut,
+    mut tracker: PositionTracker,
+    mut bars: impl IntParameter,
+    duration: Duration,
+    policy: OverlapPolicy,
+) {
+    let mut gate = PulseGate::new(gate_out, policy);
+
+    loop {
+        match select3(clock_in.wait(), reset.wait(), wait_until(gate.deadline())).await {
+            Either3::First(_) => {
+                let position = tracker.tick();
+                if position.is_every_bars(bars.get().await.max(1) as u32) {
+                    gate.trigger(duration).await;
+                }
+            }
+            Either3::Second(_) => tracker.reset(),
+            Either3::Third(()) => gate.update().await,
+        }
+    }
+}
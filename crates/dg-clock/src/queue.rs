@@ -0,0 +1,90 @@
+use core::future::pending;
+
+use embassy_futures::select::{Either, select};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, channel::Channel};
+use embassy_time::{Duration, Instant, Timer};
+
+use dg_types::ClockOut;
+
+use crate::gate::{MAX_QUEUED_PULSES, OverlapPolicy, PULSE_GAP};
+
+/// Pulses waiting to be emitted, identified by the instant at which they are due.
+///
+/// This lets a module keep listening to its input while a previously scheduled pulse is pending
+/// or being emitted.
+pub(crate) type PulseQueue<const N: usize> = Channel<NoopRawMutex, Instant, N>;
+
+/// Emits the pulses pushed into `queue`, each at its scheduled instant.
+///
+/// Pulses that are already late (e.g. because the previous one was still being emitted) are
+/// emitted right away.
+pub(crate) async fn emit_scheduled<const N: usize>(
+    queue: &PulseQueue<N>,
+    clock_out: &mut impl ClockOut,
+    duration: Duration,
+) {
+    loop {
+        let at = queue.receive().await;
+        Timer::at(at).await;
+        clock_out.emit_pulse(duration).await;
+    }
+}
+
+/// Pulses triggered while the previous one may still be emitted, identified by their duration.
+pub(crate) type TriggerQueue = Channel<NoopRawMutex, Duration, { MAX_QUEUED_PULSES as usize }>;
+
+/// Emits the pulses pushed into `triggers`, resolving overlapping pulses according to `policy`.
+///
+/// An overlapping pulse interrupts the current one by dropping its [`ClockOut::emit_pulse`]
+/// future: the pulse is then cut short with [`ClockOut::end_pulse`] in
+/// [`OverlapPolicy::Retrigger`] mode, and lengthened with [`ClockOut::hold_pulse`] in
+/// [`OverlapPolicy::Extend`] mode.
+pub(crate) async fn emit_triggered(
+    triggers: &TriggerQueue,
+    clock_out: &mut impl ClockOut,
+    policy: OverlapPolicy,
+) {
+    loop {
+        let mut duration = triggers.receive().await;
+        let mut extended = false;
+
+        loop {
+            let pulse = async {
+                if extended {
+                    clock_out.hold_pulse(duration).await;
+                } else {
+                    clock_out.emit_pulse(duration).await;
+                }
+            };
+
+            match select(pulse, overlapping_trigger(triggers, policy)).await {
+                Either::First(()) => break,
+                Either::Second(next) => {
+                    if policy == OverlapPolicy::Retrigger {
+                        clock_out.end_pulse().await;
+                        Timer::after(PULSE_GAP).await;
+                    } else {
+                        extended = true;
+                    }
+                    duration = next;
+                }
+            }
+        }
+
+        if !triggers.is_empty() {
+            Timer::after(PULSE_GAP).await;
+        }
+    }
+}
+
+/// Waits for a trigger that must interrupt the current pulse. Triggers are left in the queue in
+/// [`OverlapPolicy::Queue`] mode, and discarded in [`OverlapPolicy::Drop`] mode.
+async fn overlapping_trigger(triggers: &TriggerQueue, policy: OverlapPolicy) -> Duration {
+    match policy {
+        OverlapPolicy::Queue => pending().await,
+        OverlapPolicy::Drop => loop {
+            triggers.receive().await;
+        },
+        OverlapPolicy::Retrigger | OverlapPolicy::Extend => triggers.receive().await,
+    }
+}
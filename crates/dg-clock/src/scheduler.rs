@@ -0,0 +1,187 @@
+use core::cell::RefCell;
+
+use embassy_futures::select::select;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Instant};
+
+use crate::time::wait_until;
+
+/// Change applied to an output by an [`EventScheduler`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputAction {
+    SetHigh,
+    SetLow,
+    SetCv(u16),
+}
+
+/// Output change registered in an [`EventScheduler`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduledEvent {
+    /// Identifier of the output, interpreted by the task running the scheduler.
+    pub output: usize,
+    pub at: Instant,
+    pub action: OutputAction,
+}
+
+/// Error returned when an [`EventScheduler`] has no room left for new events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchedulerFull;
+
+struct Queue<const N: usize> {
+    /// Pending events, with the sequence number keeping events of the same instant in order.
+    slots: [Option<(u64, ScheduledEvent)>; N],
+    sequence: u64,
+}
+
+impl<const N: usize> Queue<N> {
+    fn free(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.is_none()).count()
+    }
+
+    fn push(&mut self, event: ScheduledEvent) {
+        if let Some(slot) = self.slots.iter_mut().find(|slot| slot.is_none()) {
+            *slot = Some((self.sequence, event));
+            self.sequence += 1;
+        }
+    }
+
+    fn next(&mut self) -> Option<&mut Option<(u64, ScheduledEvent)>> {
+        self.slots
+            .iter_mut()
+            .filter(|slot| slot.is_some())
+            .min_by_key(|slot| slot.map(|(sequence, event)| (event.at, sequence)))
+    }
+}
+
+/// Deadline-ordered queue of output changes, executed by a single task.
+///
+/// Instead of running one task per output, generators register future gate and CV changes with
+/// [`EventScheduler::schedule`] and [`EventScheduler::pulse`], and one task applies them at their
+/// instant with [`EventScheduler::run`]. Events of the same instant are applied in registration
+/// order. The scheduler allocates nothing: it holds at most `N` pending events, and is typically
+/// stored in a `static`.
+pub struct EventScheduler<M: RawMutex, const N: usize> {
+    queue: Mutex<M, RefCell<Queue<N>>>,
+
+    /// Wakes up the running task when the queue changes.
+    changed: Signal<M, ()>,
+}
+
+impl<M: RawMutex, const N: usize> EventScheduler<M, N> {
+    pub const fn new() -> Self {
+        Self {
+            queue: Mutex::new(RefCell::new(Queue {
+                slots: [None; N],
+                sequence: 0,
+            })),
+            changed: Signal::new(),
+        }
+    }
+
+    /// Number of pending events.
+    pub fn len(&self) -> usize {
+        self.queue.lock(|queue| N - queue.borrow().free())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Registers an output change. Events in the past are applied as soon as possible.
+    pub fn schedule(&self, event: ScheduledEvent) -> Result<(), SchedulerFull> {
+        self.schedule_all(&[event])
+    }
+
+    /// Registers a gate pulse of `duration` starting at `at`.
+    ///
+    /// Both edges are registered, or none of them if there is no room for both.
+    pub fn pulse(
+        &self,
+        output: usize,
+        at: Instant,
+        duration: Duration,
+    ) -> Result<(), SchedulerFull> {
+        self.schedule_all(&[
+            ScheduledEvent {
+                output,
+                at,
+                action: OutputAction::SetHigh,
+            },
+            ScheduledEvent {
+                output,
+                at: at + duration,
+                action: OutputAction::SetLow,
+            },
+        ])
+    }
+
+    fn schedule_all(&self, events: &[ScheduledEvent]) -> Result<(), SchedulerFull> {
+        self.queue.lock(|queue| {
+            let mut queue = queue.borrow_mut();
+            if queue.free() < events.len() {
+                return Err(SchedulerFull);
+            }
+
+            for event in events {
+                queue.push(*event);
+            }
+            Ok(())
+        })?;
+
+        self.changed.signal(());
+        Ok(())
+    }
+
+    /// Removes the pending events of `output`.
+    pub fn cancel(&self, output: usize) {
+        self.queue.lock(|queue| {
+            for slot in queue.borrow_mut().slots.iter_mut() {
+                if slot.is_some_and(|(_, event)| event.output == output) {
+                    *slot = None;
+                }
+            }
+        });
+
+        self.changed.signal(());
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        self.queue.lock(|queue| {
+            queue
+                .borrow_mut()
+                .next()
+                .and_then(|slot| slot.map(|(_, event)| event.at))
+        })
+    }
+
+    fn pop_due(&self, now: Instant) -> Option<ScheduledEvent> {
+        self.queue.lock(|queue| {
+            let mut queue = queue.borrow_mut();
+            let slot = queue.next()?;
+            match slot {
+                Some((_, event)) if event.at <= now => slot.take().map(|(_, event)| event),
+                _ => None,
+            }
+        })
+    }
+
+    /// Applies the registered events at their instant, by calling `execute` with the output
+    /// identifier and the action.
+    pub async fn run(&self, mut execute: impl AsyncFnMut(usize, OutputAction)) {
+        loop {
+            while let Some(event) = self.pop_due(Instant::now()) {
+                execute(event.output, event.action).await;
+            }
+
+            select(self.changed.wait(), wait_until(self.next_deadline())).await;
+        }
+    }
+}
+
+impl<M: RawMutex, const N: usize> Default for EventScheduler<M, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,308 @@
+use embassy_futures::join::join;
+use embassy_futures::select::{Either, select};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, channel::Channel};
+use embassy_time::{Duration, Instant, Timer};
+use rand::Rng;
+use rand_core::RngCore;
+
+use dg_types::{ClockIn, ClockOut, IntParameter};
+
+use crate::PeriodMeter;
+use crate::time::scale_duration;
+
+/// Maximum number of steps in a [`Pattern`].
+pub const MAX_STEPS: usize = 64;
+
+/// Maximum number of pulses emitted by a single step.
+pub const MAX_RATCHETS: u8 = 8;
+
+/// Pulses scheduled by a single clock: the current step and the next one, if played early.
+const MAX_PENDING_STEP_PULSES: usize = 2 * MAX_RATCHETS as usize;
+
+/// Pulses waiting to be emitted, with their duration.
+type StepQueue = Channel<NoopRawMutex, (Instant, Duration), MAX_PENDING_STEP_PULSES>;
+
+/// Elektron-style trig condition, deciding on which pattern iterations a step plays.
+///
+/// Iterations are counted from 0 since the sequencer started or was last reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrigCondition {
+    #[default]
+    Always,
+
+    /// `A:B`: plays on the `a`-th iteration of every `b` (e.g. `1:2` plays every other iteration,
+    /// starting with the first one).
+    Ratio { a: u8, b: u8 },
+
+    /// Plays only while fill mode is active.
+    Fill,
+
+    /// Plays only while fill mode is inactive.
+    NotFill,
+
+    /// Plays on the first iteration only.
+    First,
+
+    /// Plays on every iteration but the first.
+    NotFirst,
+}
+
+impl TrigCondition {
+    /// Whether a step with this condition plays on the given pattern iteration.
+    pub fn holds(self, iteration: u32, fill: bool) -> bool {
+        match self {
+            TrigCondition::Always => true,
+            TrigCondition::Ratio { a, b } => {
+                let b = b.max(1) as u32;
+                iteration % b == (a.max(1) as u32 - 1) % b
+            }
+            TrigCondition::Fill => fill,
+            TrigCondition::NotFill => !fill,
+            TrigCondition::First => iteration == 0,
+            TrigCondition::NotFirst => iteration != 0,
+        }
+    }
+}
+
+/// A single step of a [`Pattern`].
+///
+/// Steps are built with `const` methods, e.g. `Step::TRIG.with_ratchets(2)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Step {
+    pub active: bool,
+
+    /// Probability (`0.0..=1.0`) that the step plays when its condition holds.
+    pub probability: f32,
+
+    /// Number of evenly spaced pulses within the step (up to [`MAX_RATCHETS`]).
+    pub ratchets: u8,
+
+    /// Micro-timing offset, as a fraction of the step length in `-0.5..=0.5`.
+    pub offset: f32,
+
+    pub condition: TrigCondition,
+}
+
+impl Step {
+    pub const OFF: Step = Step {
+        active: false,
+        probability: 1.0,
+        ratchets: 1,
+        offset: 0.0,
+        condition: TrigCondition::Always,
+    };
+
+    pub const TRIG: Step = Step {
+        active: true,
+        ..Step::OFF
+    };
+
+    pub const fn with_probability(self, probability: f32) -> Self {
+        Self {
+            probability,
+            ..self
+        }
+    }
+
+    pub const fn with_ratchets(self, ratchets: u8) -> Self {
+        Self { ratchets, ..self }
+    }
+
+    pub const fn with_offset(self, offset: f32) -> Self {
+        Self { offset, ..self }
+    }
+
+    pub const fn with_condition(self, condition: TrigCondition) -> Self {
+        Self { condition, ..self }
+    }
+
+    fn ratchet_count(&self) -> u8 {
+        self.ratchets.clamp(1, MAX_RATCHETS)
+    }
+
+    fn offset(&self) -> f32 {
+        self.offset.clamp(-0.5, 0.5)
+    }
+
+    /// Decides whether the step plays on this iteration.
+    fn fires(&self, iteration: u32, fill: bool, rng: &mut impl RngCore) -> bool {
+        self.active
+            && self.condition.holds(iteration, fill)
+            && (self.probability >= 1.0 || rng.r#gen::<f32>() < self.probability)
+    }
+}
+
+/// Sequence of up to [`MAX_STEPS`] steps, which can be embedded in firmware as a `const`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    steps: [Step; MAX_STEPS],
+    length: usize,
+}
+
+impl Pattern {
+    /// Panics (at compile time in a `const` context) if `steps` is empty or longer than
+    /// [`MAX_STEPS`].
+    pub const fn new(steps: &[Step]) -> Self {
+        assert!(!steps.is_empty() && steps.len() <= MAX_STEPS);
+
+        let mut pattern = Pattern {
+            steps: [Step::OFF; MAX_STEPS],
+            length: steps.len(),
+        };
+
+        let mut i = 0;
+        while i < steps.len() {
+            pattern.steps[i] = steps[i];
+            i += 1;
+        }
+
+        pattern
+    }
+
+    pub const fn length(&self) -> usize {
+        self.length
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps[..self.length]
+    }
+}
+
+struct Playhead {
+    /// Index of the step played on the next clock.
+    position: usize,
+
+    /// Number of completed pattern iterations.
+    iteration: u32,
+
+    /// Whether the step at `position` was already handled ahead of its clock, because of a
+    /// negative micro-timing offset.
+    handled: bool,
+}
+
+impl Playhead {
+    const fn new() -> Self {
+        Self {
+            position: 0,
+            iteration: 0,
+            handled: false,
+        }
+    }
+
+    fn advance(&mut self, length: usize) {
+        self.position += 1;
+        if self.position >= length {
+            self.position = 0;
+            self.iteration = self.iteration.wrapping_add(1);
+        }
+        self.handled = false;
+    }
+}
+
+/// Schedules the pulses of a step starting at `start`.
+fn schedule(
+    pulses: &mut [(Instant, Duration); MAX_PENDING_STEP_PULSES],
+    count: &mut usize,
+    step: &Step,
+    start: Instant,
+    period: Option<Duration>,
+    duration: Duration,
+) {
+    let mut push = |pulse| {
+        if *count < pulses.len() {
+            pulses[*count] = pulse;
+            *count += 1;
+        }
+    };
+
+    // ratchets need the step length, only a single pulse is emitted until it is known
+    let Some(period) = period else {
+        push((start, duration));
+        return;
+    };
+
+    let ratchets = step.ratchet_count() as u32;
+    let spacing = period / ratchets;
+    for i in 0..ratchets {
+        push((start + spacing * i, duration.min(spacing / 2)));
+    }
+}
+
+/// Step trigger sequencer.
+///
+/// Each clock plays the next step of `pattern` and an edge on `reset` brings the sequencer back to
+/// the first step and iteration. `fill` is non-zero while fill mode is active.
+///
+/// Ratchets and micro-timing are relative to the step length, measured as the clock period. A
+/// step with a negative offset is played ahead of its clock, which requires deciding whether it
+/// fires on the previous clock. Until the period is known, steps are played on their clock, as a
+/// single pulse.
+pub async fn step_sequencer(
+    mut clock_in: impl ClockIn,
+    mut reset: impl ClockIn,
+    mut clock_out: impl ClockOut,
+    pattern: &Pattern,
+    mut fill: impl IntParameter,
+    mut rng: impl RngCore,
+    duration: Duration,
+) {
+    let queue = StepQueue::new();
+
+    let input = async {
+        let mut meter = PeriodMeter::new();
+        let mut playhead = Playhead::new();
+
+        loop {
+            let now = match select(clock_in.wait(), reset.wait()).await {
+                Either::First(now) => now,
+                Either::Second(_) => {
+                    playhead = Playhead::new();
+                    queue.clear();
+                    continue;
+                }
+            };
+
+            let period = meter.tick(now);
+            let fill = fill.get().await != 0;
+            let steps = pattern.steps();
+
+            let mut pulses = [(now, duration); MAX_PENDING_STEP_PULSES];
+            let mut count = 0;
+
+            let step = &steps[playhead.position];
+            if !playhead.handled && step.fires(playhead.iteration, fill, &mut rng) {
+                let delay = period.map_or(Duration::from_ticks(0), |period| {
+                    scale_duration(period, step.offset().max(0.0))
+                });
+                schedule(&mut pulses, &mut count, step, now + delay, period, duration);
+            }
+
+            playhead.advance(steps.len());
+
+            // look ahead for a step which should be played before its clock
+            let next = &steps[playhead.position];
+            if let Some(period) = period.filter(|_| next.offset() < 0.0) {
+                playhead.handled = true;
+                if next.fires(playhead.iteration, fill, &mut rng) {
+                    let start = now + scale_duration(period, 1.0 + next.offset());
+                    schedule(&mut pulses, &mut count, next, start, Some(period), duration);
+                }
+            }
+
+            pulses[..count].sort_unstable_by_key(|(at, _)| *at);
+            for pulse in &pulses[..count] {
+                let _ = queue.try_send(*pulse);
+            }
+        }
+    };
+
+    let output = async {
+        loop {
+            let (at, duration) = queue.receive().await;
+            Timer::at(at).await;
+            clock_out.emit_pulse(duration).await;
+        }
+    };
+
+    join(input, output).await;
+}
@@ -0,0 +1,130 @@
+use embassy_futures::select::{Either, select};
+
+use dg_types::{ClockIn, CvOut, FloatParameter, IntParameter};
+
+/// Order in which a [`Staircase`] goes through its steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StaircaseDirection {
+    #[default]
+    Up,
+    Down,
+
+    /// Up then down, without repeating the first and last steps.
+    PingPong,
+}
+
+/// Clock counter going through `0..length` steps, e.g. to index a sequence.
+#[derive(Debug, Clone)]
+pub struct Staircase {
+    direction: StaircaseDirection,
+
+    /// Current step, `None` until the first clock after a reset.
+    position: Option<u32>,
+
+    /// Whether a ping-pong staircase is currently going up.
+    rising: bool,
+}
+
+impl Staircase {
+    pub const fn new(direction: StaircaseDirection) -> Self {
+        Self {
+            direction,
+            position: None,
+            rising: true,
+        }
+    }
+
+    pub fn direction(&self) -> StaircaseDirection {
+        self.direction
+    }
+
+    pub fn set_direction(&mut self, direction: StaircaseDirection) {
+        self.direction = direction;
+    }
+
+    /// Current step, if any clock was received since the last reset.
+    pub fn position(&self) -> Option<u32> {
+        self.position
+    }
+
+    /// Makes the next step the first one.
+    pub fn reset(&mut self) {
+        self.position = None;
+        self.rising = true;
+    }
+
+    /// Moves to the next step of a `length` step staircase and returns it.
+    ///
+    /// When the length shrinks below the current step, the staircase continues from its last
+    /// step.
+    pub fn step(&mut self, length: u32) -> u32 {
+        let last = length.max(1) - 1;
+
+        let next = match self.position.map(|position| position.min(last)) {
+            None if self.direction == StaircaseDirection::Down => last,
+            None => 0,
+            Some(_) if last == 0 => 0,
+            Some(position) => match self.direction {
+                StaircaseDirection::Up if position >= last => 0,
+                StaircaseDirection::Up => position + 1,
+                StaircaseDirection::Down if position == 0 => last,
+                StaircaseDirection::Down => position - 1,
+                StaircaseDirection::PingPong => {
+                    if self.rising && position >= last {
+                        self.rising = false;
+                    } else if !self.rising && position == 0 {
+                        self.rising = true;
+                    }
+
+                    if self.rising {
+                        position + 1
+                    } else {
+                        position - 1
+                    }
+                }
+            },
+        };
+
+        self.position = Some(next);
+        next
+    }
+
+    /// CV output value of the current step, with the last step of a `length` step staircase
+    /// reaching `range` (`0.0..=1.0`) of the whole CV output range.
+    pub fn value(&self, length: u32, range: f32) -> u16 {
+        let last = length.max(1) - 1;
+        let Some(position) = self.position.filter(|_| last > 0) else {
+            return 0;
+        };
+
+        let level = position.min(last) as f32 / last as f32 * range.clamp(0.0, 1.0);
+        (level * u16::MAX as f32 + 0.5) as u16
+    }
+}
+
+/// Clock to staircase CV converter.
+///
+/// Each clock moves `staircase` to its next step, out of `length` (read on every clock), and
+/// `cv_out` is set to the corresponding level, evenly spread over `range` of the output range.
+/// An edge on `reset` makes the next clock go back to the first step.
+pub async fn staircase(
+    mut clock_in: impl ClockIn,
+    mut reset: impl ClockIn,
+    mut cv_out: impl CvOut,
+    mut staircase: Staircase,
+    mut length: impl IntParameter,
+    mut range: impl FloatParameter,
+) {
+    loop {
+        match select(clock_in.wait(), reset.wait()).await {
+            Either::First(_) => {
+                let length = length.get().await.max(1) as u32;
+                staircase.step(length);
+                cv_out
+                    .set_value(staircase.value(length, range.get().await))
+                    .await;
+            }
+            Either::Second(_) => staircase.reset(),
+        }
+    }
+}
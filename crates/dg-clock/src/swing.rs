@@ -0,0 +1,87 @@
+use embassy_futures::join::join;
+use embassy_time::Duration;
+
+use dg_types::{ClockIn, ClockOut, FloatParameter};
+
+use crate::queue::{PulseQueue, emit_scheduled};
+use crate::time::scale_duration;
+use crate::{MAX_PENDING_PULSES, PeriodMeter};
+
+/// Largest swing amount, as a fraction of the grid step.
+///
+/// Keeping it below 1.0 guarantees that a swung pulse is emitted before the next one.
+pub const MAX_SWING_AMOUNT: f32 = 0.75;
+
+/// Note grid on which swing is applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwingGrid {
+    Eighth,
+    Sixteenth,
+}
+
+impl SwingGrid {
+    /// Number of incoming pulses per grid step, for a clock running at `ppqn` pulses per quarter
+    /// note.
+    pub const fn pulses_per_step(self, ppqn: u32) -> u32 {
+        let pulses = match self {
+            SwingGrid::Eighth => ppqn / 2,
+            SwingGrid::Sixteenth => ppqn / 4,
+        };
+
+        if pulses == 0 { 1 } else { pulses }
+    }
+}
+
+/// Swing/shuffle processor.
+///
+/// The incoming clock runs at `ppqn` pulses per quarter note, and every `every`-th step of the
+/// chosen `grid` is delayed by `amount` times the measured step period. `amount` is clamped to
+/// `0.0..=MAX_SWING_AMOUNT`. Every incoming pulse is passed through: when a step spans several
+/// pulses, the pulses of a swung step are squeezed into what remains of it, so that the output
+/// keeps the resolution and the order of the input. Pulses are emitted straight until a period
+/// has been measured.
+pub async fn swing(
+    mut clock_in: impl ClockIn,
+    mut clock_out: impl ClockOut,
+    mut amount: impl FloatParameter,
+    grid: SwingGrid,
+    ppqn: u32,
+    every: u32,
+    duration: Duration,
+) {
+    let pulses_per_step = grid.pulses_per_step(ppqn);
+    let every = every.max(1);
+    let queue = PulseQueue::<MAX_PENDING_PULSES>::new();
+
+    let input = async {
+        let mut meter = PeriodMeter::new();
+        let mut pulse_index = 0;
+        let mut swing_amount = 0.0;
+
+        loop {
+            let instant = clock_in.wait().await;
+            let period = meter.tick(instant);
+
+            let index = pulse_index;
+            pulse_index = (pulse_index + 1) % (pulses_per_step * every);
+
+            let mut at = instant;
+            if index / pulses_per_step == every - 1 {
+                // pulse `step_index` of the swung step is delayed by the swing amount times the
+                // `pulses_per_step - step_index` pulse periods left in the step
+                let step_index = index % pulses_per_step;
+                if step_index == 0 {
+                    swing_amount = amount.get().await.clamp(0.0, MAX_SWING_AMOUNT);
+                }
+                if let Some(period) = period {
+                    at += scale_duration(period * (pulses_per_step - step_index), swing_amount);
+                }
+            }
+
+            // the queue can only be full if the output is hopelessly late, so dropping is fine
+            let _ = queue.try_send(at);
+        }
+    };
+
+    join(input, emit_scheduled(&queue, &mut clock_out, duration)).await;
+}
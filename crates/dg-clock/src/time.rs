@@ -0,0 +1,29 @@
+use embassy_time::{Duration, Instant, Timer};
+
+use crate::PulseGrid;
+
+/// Scales a duration by a floating point factor, rounding to the nearest microsecond.
+pub(crate) fn scale_duration(duration: Duration, factor: f32) -> Duration {
+    Duration::from_micros(libm::roundf(duration.as_micros() as f32 * factor.max(0.0)) as u64)
+}
+
+/// Period of a clock running at `bpm` beats per minute.
+pub(crate) fn bpm_period(bpm: f32) -> Duration {
+    Duration::from_micros(((60.0 * 1_000_000.0) / bpm) as u64)
+}
+
+/// Waits until `deadline`, or forever if there is none.
+pub(crate) async fn wait_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => Timer::at(deadline).await,
+        None => core::future::pending().await,
+    }
+}
+
+/// Waits for the next deadline of `grid` with [`PulseGrid::wait`], or forever if there is none.
+pub(crate) async fn wait_grid(grid: Option<&mut PulseGrid>) -> Instant {
+    match grid {
+        Some(grid) => grid.wait().await,
+        None => core::future::pending().await,
+    }
+}
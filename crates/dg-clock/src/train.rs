@@ -0,0 +1,227 @@
+use embassy_futures::join::join;
+use embassy_futures::select::{Either, select};
+use embassy_time::Duration;
+
+use dg_types::{ClockIn, ClockOut, FloatParameter, IntParameter};
+
+use crate::queue::{TriggerQueue, emit_triggered};
+use crate::time::{bpm_period, wait_grid};
+use crate::{MAX_QUEUED_PULSES, OverlapPolicy, PeriodMeter, PulseGrid};
+
+/// Longest pulse emitted by the train generators.
+const MAX_PULSE_WIDTH: Duration = Duration::from_millis(10);
+
+/// How [`clock_train`] spaces the pulses of a train.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrainMode {
+    /// The pulses are evenly spaced at the `pulse_bpm` rate.
+    ///
+    /// The policy decides what happens when a clock arrives while a train is running: with
+    /// [`OverlapPolicy::Drop`] the clock is ignored, with [`OverlapPolicy::Retrigger`] the train
+    /// starts over, with [`OverlapPolicy::Extend`] the running train is extended to a full count of
+    /// pulses, and with [`OverlapPolicy::Queue`] a new train follows the running one.
+    ///
+    /// Pulses are scheduled on absolute deadlines from the instant of the clock (see
+    /// [`PulseGrid`]), so long trains don't drift.
+    Fixed(OverlapPolicy),
+
+    /// Ratchet: the pulses are fitted into the measured clock period and spaced according to
+    /// `curve` (see [`ratchet_offset`]). The policy decides what happens when a new clock arrives
+    /// mid-burst. Until the period is known, a single pulse is emitted per clock.
+    Ratchet(BurstPolicy),
+}
+
+impl Default for TrainMode {
+    fn default() -> Self {
+        Self::Fixed(OverlapPolicy::default())
+    }
+}
+
+/// Emits a train of pulse for each incoming clock signal.
+///
+/// `pulse_bpm` is only used in [`TrainMode::Fixed`], and `curve` only in [`TrainMode::Ratchet`].
+pub async fn clock_train(
+    clock_in: impl ClockIn,
+    clock_out: impl ClockOut,
+    pulse_count: impl IntParameter,
+    pulse_bpm: impl FloatParameter,
+    curve: impl FloatParameter,
+    mode: TrainMode,
+) {
+    match mode {
+        TrainMode::Fixed(policy) => {
+            fixed_train(clock_in, clock_out, pulse_count, pulse_bpm, policy).await
+        }
+        TrainMode::Ratchet(policy) => {
+            ratchet_train(clock_in, clock_out, pulse_count, curve, policy).await
+        }
+    }
+}
+
+/// What happens when a new clock arrives while a ratchet burst is still running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BurstPolicy {
+    /// The remaining pulses of the current burst are dropped, and the new clock is ignored.
+    Truncate,
+
+    /// The current burst runs to completion, and the new clock is ignored.
+    Finish,
+
+    /// The current burst is abandoned and the new clock starts a new one.
+    #[default]
+    Restart,
+}
+
+/// Time offset of pulse `index` in a burst of `count` pulses fitted into `period`.
+///
+/// With a `curve` of 0.0, pulses are evenly spaced. Positive values make the burst decelerate
+/// (pulses bunched at the start), negative values make it accelerate like a bouncing ball (pulses
+/// bunched at the end). `curve` is clamped to `-1.0..=1.0`.
+pub fn ratchet_offset(period: Duration, index: u32, count: u32, curve: f32) -> Duration {
+    if count == 0 {
+        return Duration::from_ticks(0);
+    }
+
+    let exponent = libm::powf(4.0, curve.clamp(-1.0, 1.0));
+    let position = libm::powf(index as f32 / count as f32, exponent);
+
+    Duration::from_micros((period.as_micros() as f32 * position) as u64)
+}
+
+struct Burst {
+    grid: PulseGrid,
+    count: u32,
+}
+
+impl Burst {
+    /// Width of the pulse at `index`, half the gap to the following one at most.
+    fn pulse_width(&self, index: u32) -> Duration {
+        let gap = self.grid.at(index + 1) - self.grid.at(index);
+        MAX_PULSE_WIDTH.min(gap / 2)
+    }
+}
+
+async fn ratchet_train(
+    mut clock_in: impl ClockIn,
+    mut clock_out: impl ClockOut,
+    mut pulse_count: impl IntParameter,
+    mut curve: impl FloatParameter,
+    policy: BurstPolicy,
+) {
+    let mut meter = PeriodMeter::new();
+    let mut burst: Option<Burst> = None;
+
+    loop {
+        let grid = burst.as_mut().map(|burst| &mut burst.grid);
+
+        match select(clock_in.wait(), wait_grid(grid)).await {
+            Either::First(instant) => {
+                let period = meter.tick(instant);
+
+                if burst.is_some() {
+                    match policy {
+                        BurstPolicy::Truncate => {
+                            burst = None;
+                            continue;
+                        }
+                        BurstPolicy::Finish => continue,
+                        BurstPolicy::Restart => {}
+                    }
+                }
+
+                let count = pulse_count.get().await.max(0) as u32;
+                let (period, count) = match period {
+                    Some(period) => (period, count),
+                    None => (MAX_PULSE_WIDTH * 2, count.min(1)),
+                };
+
+                burst = (count > 0).then_some(Burst {
+                    grid: PulseGrid::curved(instant, period, count, curve.get().await),
+                    count,
+                });
+            }
+
+            Either::Second(_) => {
+                let Some(current) = burst.as_mut() else {
+                    continue;
+                };
+
+                // the grid has already moved past the pulse being emitted
+                let index = current.grid.index() - 1;
+                clock_out.emit_pulse(current.pulse_width(index)).await;
+
+                if current.grid.index() >= current.count {
+                    burst = None;
+                }
+            }
+        }
+    }
+}
+
+struct Train {
+    grid: PulseGrid,
+    count: u32,
+}
+
+async fn fixed_train(
+    mut clock_in: impl ClockIn,
+    mut clock_out: impl ClockOut,
+    mut pulse_count: impl IntParameter,
+    mut pulse_bpm: impl FloatParameter,
+    policy: OverlapPolicy,
+) {
+    let triggers = TriggerQueue::new();
+
+    let trains = async {
+        let mut train: Option<Train> = None;
+        let mut queued = 0;
+
+        loop {
+            let grid = train.as_mut().map(|train| &mut train.grid);
+
+            match select(clock_in.wait(), wait_grid(grid)).await {
+                Either::First(instant) => {
+                    let count = pulse_count.get().await.max(0) as u32;
+                    let period = bpm_period(pulse_bpm.get().await);
+
+                    match (train.as_mut(), policy) {
+                        (Some(_), OverlapPolicy::Drop) => {}
+                        (Some(current), OverlapPolicy::Extend) => {
+                            current.count = current.grid.index() + count;
+                        }
+                        (Some(_), OverlapPolicy::Queue) => {
+                            queued = (queued + 1).min(MAX_QUEUED_PULSES);
+                        }
+                        (None, _) | (Some(_), OverlapPolicy::Retrigger) => {
+                            train = (count > 0).then_some(Train {
+                                grid: PulseGrid::new(instant, period),
+                                count,
+                            });
+                        }
+                    }
+                }
+
+                Either::Second(_) => {
+                    let Some(current) = train.as_mut() else {
+                        continue;
+                    };
+
+                    let _ = triggers.try_send(MAX_PULSE_WIDTH.min(current.grid.period() / 2));
+
+                    if current.grid.index() >= current.count {
+                        if queued > 0 {
+                            // the queued train follows on the same grid
+                            queued -= 1;
+                            current.grid =
+                                PulseGrid::new(current.grid.deadline(), current.grid.period());
+                        } else {
+                            train = None;
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    join(trains, emit_triggered(&triggers, &mut clock_out, policy)).await;
+}
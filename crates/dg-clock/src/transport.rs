@@ -0,0 +1,148 @@
+use embassy_futures::select::select;
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::watch::Watch;
+
+use dg_types::{ClockIn, GateIn};
+
+/// State of a [`Transport`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransportState {
+    Running,
+
+    #[default]
+    Stopped,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Status {
+    state: TransportState,
+
+    /// Incremented on every start, so that followers notice a stop immediately followed by a
+    /// start.
+    starts: u32,
+}
+
+/// Run/stop state shared between the tasks of a patch.
+///
+/// Generators wrapped with [`Transport::follow`] only run while the transport is running. They
+/// are restarted from scratch on every start (reset-on-start), so that sequencers go back to
+/// their first step and internal clocks re-align their phase. The transport is typically stored
+/// in a `static`, so that any task can start or stop it. `N` is the maximum number of followers.
+pub struct Transport<M: RawMutex, const N: usize> {
+    status: Watch<M, Status, N>,
+}
+
+impl<M: RawMutex, const N: usize> Transport<M, N> {
+    /// Creates a stopped transport.
+    pub const fn new() -> Self {
+        Self {
+            status: Watch::new(),
+        }
+    }
+
+    pub fn state(&self) -> TransportState {
+        self.status
+            .try_get()
+            .map_or(TransportState::Stopped, |status| status.state)
+    }
+
+    /// Starts the transport, unless it is already running.
+    pub fn start(&self) {
+        if self.state() == TransportState::Stopped {
+            self.restart();
+        }
+    }
+
+    /// Starts the transport, restarting all followers if it was already running.
+    pub fn restart(&self) {
+        self.status.sender().send_modify(|status| {
+            let starts = status.map_or(0, |status| status.starts.wrapping_add(1));
+            *status = Some(Status {
+                state: TransportState::Running,
+                starts,
+            });
+        });
+    }
+
+    pub fn stop(&self) {
+        self.status
+            .sender()
+            .send_if_modified(|status| match status {
+                Some(status) if status.state == TransportState::Running => {
+                    status.state = TransportState::Stopped;
+                    true
+                }
+                _ => false,
+            });
+    }
+
+    pub fn toggle(&self) {
+        match self.state() {
+            TransportState::Running => self.stop(),
+            TransportState::Stopped => self.start(),
+        }
+    }
+
+    /// Runs the generator returned by `generator` while the transport is running.
+    ///
+    /// The generator is cancelled when the transport stops or restarts, after which `stopped` is
+    /// called with `outputs` so that nothing is left hanging, e.g. to set gates low or to send a
+    /// MIDI stop. A fresh generator is created on every start.
+    ///
+    /// Panics if the transport already has `N` followers.
+    pub async fn follow<O>(
+        &self,
+        outputs: &mut O,
+        mut generator: impl AsyncFnMut(&mut O),
+        mut stopped: impl AsyncFnMut(&mut O),
+    ) {
+        let mut receiver = self
+            .status
+            .receiver()
+            .expect("too many transport followers");
+
+        loop {
+            let status = receiver
+                .get_and(|status| status.state == TransportState::Running)
+                .await;
+
+            select(
+                generator(outputs),
+                receiver.changed_and(|new| *new != status),
+            )
+            .await;
+
+            stopped(outputs).await;
+        }
+    }
+}
+
+impl<M: RawMutex, const N: usize> Default for Transport<M, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Toggles the transport on every edge of `button`.
+pub async fn transport_button<M: RawMutex, const N: usize>(
+    transport: &Transport<M, N>,
+    mut button: impl ClockIn,
+) {
+    loop {
+        button.wait().await;
+        transport.toggle();
+    }
+}
+
+/// Runs the transport while `run` is high.
+pub async fn transport_gate<M: RawMutex, const N: usize>(
+    transport: &Transport<M, N>,
+    mut run: impl GateIn,
+) {
+    loop {
+        run.wait_for_high().await;
+        transport.start();
+        run.wait_for_low().await;
+        transport.stop();
+    }
+}
@@ -0,0 +1,41 @@
+use embassy_futures::select::{Either, select};
+use embassy_time::Duration;
+
+use dg_noise::{NoiseGenerator, TuringMachine};
+use dg_types::{ClockIn, CvOut, FloatParameter, GateOut, IntParameter};
+
+use crate::OverlapPolicy;
+use crate::gate::PulseGate;
+use crate::time::wait_until;
+
+/// Looping random sequencer driven by a [`TuringMachine`].
+///
+/// On each clock, the register is shifted with the flip probability read from `probability`
+/// (0 locks the loop, 1 makes it fully random) and its loop length from `length` (2 to 16 steps).
+/// The CV output is then updated from the register, and a pulse is emitted if its top bit is set.
+pub async fn turing_machine(
+    mut clock_in: impl ClockIn,
+    gate_out: impl GateOut,
+    mut cv_out: impl CvOut,
+    mut machine: TuringMachine<impl NoiseGenerator>,
+    mut probability: impl FloatParameter,
+    mut length: impl IntParameter,
+    duration: Duration,
+) {
+    let mut gate = PulseGate::new(gate_out, OverlapPolicy::Drop);
+
+    loop {
+        match select(clock_in.wait(), wait_until(gate.deadline())).await {
+            Either::First(_) => {
+                machine.set_length(length.get().await.clamp(0, u8::MAX as i32) as u8);
+                machine.step(probability.get().await);
+
+                cv_out.set_value(machine.cv()).await;
+                if machine.gate() {
+                    gate.trigger(duration).await;
+                }
+            }
+            Either::Second(()) => gate.update().await,
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use embassy_time::{Duration, Instant};
+
+use dg_clock::{BernoulliGate, BernoulliMode, BernoulliOutput};
+
+mod common;
+
+use common::{MockClockIn, MockGateOut, gate_pulses, millis, run_for};
+
+const TOSSES: usize = 10_000;
+
+fn count_b(gate: &mut BernoulliGate<impl rand_core::RngCore>, probability: f32) -> usize {
+    (0..TOSSES)
+        .filter(|_| gate.toss(probability) == BernoulliOutput::B)
+        .count()
+}
+
+#[test]
+fn test_trigger_distribution() {
+    for probability in [0.1, 0.25, 0.5, 0.9] {
+        let mut gate = BernoulliGate::from_seed(42, BernoulliMode::Trigger);
+        let count = count_b(&mut gate, probability) as f32;
+
+        // 4 standard deviations
+        let expected = TOSSES as f32 * probability;
+        let tolerance = 4.0 * (TOSSES as f32 * probability * (1.0 - probability)).sqrt();
+        assert!(
+            (count - expected).abs() < tolerance,
+            "p={probability}: {count} hits, expected {expected}±{tolerance}"
+        );
+    }
+}
+
+#[test]
+fn test_trigger_extremes() {
+    let mut gate = BernoulliGate::from_seed(1, BernoulliMode::Trigger);
+    assert_eq!(count_b(&mut gate, 0.0), 0);
+    assert_eq!(count_b(&mut gate, 1.0), TOSSES);
+    assert_eq!(count_b(&mut gate, -3.0), 0);
+    assert_eq!(count_b(&mut gate, 3.0), TOSSES);
+}
+
+#[test]
+fn test_toggle_distribution() {
+    let mut gate = BernoulliGate::from_seed(42, BernoulliMode::Toggle);
+    let mut previous = BernoulliOutput::A;
+    let mut switches = 0;
+    let mut b_count = 0;
+
+    for _ in 0..TOSSES {
+        let output = gate.toss(0.3);
+        if output != previous {
+            switches += 1;
+        }
+        if output == BernoulliOutput::B {
+            b_count += 1;
+        }
+        previous = output;
+    }
+
+    assert!((2700..3300).contains(&switches), "{switches} switches");
+
+    // in the long run, both outputs are equally likely
+    assert!((4000..6000).contains(&b_count), "{b_count} hits on B");
+
+    // never switches at p=0, always switches at p=1
+    let current = gate.toss(0.0);
+    assert!((0..10).all(|_| gate.toss(0.0) == current));
+    assert!((0..10).all(|i| (gate.toss(1.0) == current) == (i % 2 == 1)));
+}
+
+#[test]
+fn test_seeded_gate_is_deterministic() {
+    let mut gate_1 = BernoulliGate::from_seed(1234, BernoulliMode::Trigger);
+    let mut gate_2 = BernoulliGate::from_seed(1234, BernoulliMode::Trigger);
+
+    for _ in 0..1000 {
+        assert_eq!(gate_1.toss(0.5), gate_2.toss(0.5));
+    }
+}
+
+#[tokio::test]
+async fn test_bernoulli_gate_routing() {
+    let now = Instant::now();
+    let mut edges_a = Vec::new();
+    let mut edges_b = Vec::new();
+
+    run_for(
+        dg_clock::bernoulli_gate(
+            MockClockIn::new(millis(now, &[10, 20, 30])),
+            MockGateOut::new(&mut edges_a),
+            MockGateOut::new(&mut edges_b),
+            1.0,
+            BernoulliGate::from_seed(0, BernoulliMode::Trigger),
+            Duration::from_millis(5),
+        ),
+        Duration::from_millis(50),
+    )
+    .await;
+
+    assert!(edges_a.is_empty());
+
+    let pulses = gate_pulses(&edges_b);
+    assert_eq!(pulses.len(), 3);
+    for (pulse, at) in pulses.iter().zip([10, 20, 30]) {
+        pulse.assert_shortly_after(now + Duration::from_millis(at));
+        assert!(pulse.duration() >= Duration::from_millis(5));
+    }
+}
+
+#[tokio::test]
+async fn test_bernoulli_gate_latch() {
+    let now = Instant::now();
+    let mut edges_a = Vec::new();
+    let mut edges_b = Vec::new();
+
+    run_for(
+        dg_clock::bernoulli_gate(
+            MockClockIn::new(millis(now, &[10, 20])),
+            MockGateOut::new(&mut edges_a),
+            MockGateOut::new(&mut edges_b),
+            0.0,
+            BernoulliGate::from_seed(0, BernoulliMode::Latch),
+            Duration::from_millis(5),
+        ),
+        Duration::from_millis(40),
+    )
+    .await;
+
+    // A is latched high and never released, B is kept low
+    assert_eq!(edges_a.len(), 2);
+    assert!(edges_a.iter().all(|(_, high)| *high));
+    assert!(gate_pulses(&edges_a).is_empty());
+    assert!(edges_b.iter().all(|(_, high)| !*high));
+}
@@ -0,0 +1,110 @@
+use embassy_futures::join::join3;
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use embassy_time::{Duration, Instant};
+
+use dg_clock::ClockBus;
+use dg_types::ClockIn;
+
+mod common;
+
+use common::{MockClockIn, millis, run_for};
+
+type TestBus = ClockBus<NoopRawMutex, 4, 2>;
+
+#[tokio::test]
+async fn test_clock_bus_fan_out() {
+    let bus = TestBus::new();
+    let now = Instant::now();
+    let instants = millis(now, &[10, 20, 21, 30, 45]);
+
+    let mut first = bus.subscriber().unwrap();
+    let mut second = bus.subscriber().unwrap();
+    let mut first_pulses = Vec::new();
+    let mut second_pulses = Vec::new();
+
+    run_for(
+        join3(
+            bus.feed(MockClockIn::new(instants.clone())),
+            async {
+                loop {
+                    first_pulses.push(first.wait().await);
+                }
+            },
+            async {
+                loop {
+                    second_pulses.push(second.wait().await);
+                }
+            },
+        ),
+        Duration::from_millis(60),
+    )
+    .await;
+
+    // both subscribers get the original edge instants, in order
+    assert_eq!(first_pulses, instants);
+    assert_eq!(second_pulses, instants);
+    assert_eq!(first.lagged(), 0);
+    assert_eq!(second.lagged(), 0);
+}
+
+#[tokio::test]
+async fn test_clock_bus_lag() {
+    let bus = TestBus::new();
+    let now = Instant::now();
+    let instants = millis(now, &[1, 2, 3, 4, 5, 6, 7]);
+
+    let mut fast = bus.subscriber().unwrap();
+    let mut slow = bus.subscriber().unwrap();
+
+    for (i, instant) in instants.iter().enumerate() {
+        bus.publish(*instant);
+        if i < 2 {
+            assert_eq!(fast.wait().await, *instant);
+        }
+    }
+
+    // the slow subscriber skips the 3 oldest pulses, the fast one only missed a single pulse
+    // since it last read
+    assert_eq!(slow.wait().await, instants[3]);
+    assert_eq!(slow.lagged(), 3);
+    assert_eq!(fast.wait().await, instants[3]);
+    assert_eq!(fast.lagged(), 1);
+
+    for instant in &instants[4..] {
+        assert_eq!(slow.wait().await, *instant);
+        assert_eq!(fast.wait().await, *instant);
+    }
+    assert_eq!(slow.lagged(), 3);
+    assert_eq!(fast.lagged(), 1);
+}
+
+#[tokio::test]
+async fn test_clock_bus_late_subscriber() {
+    let bus = TestBus::new();
+    let now = Instant::now();
+
+    let mut early = bus.subscriber().unwrap();
+    bus.publish(now);
+
+    // a subscriber only receives the pulses published after its creation
+    let mut late = bus.subscriber().unwrap();
+    bus.publish(now + Duration::from_millis(10));
+
+    assert_eq!(early.wait().await, now);
+    assert_eq!(early.wait().await, now + Duration::from_millis(10));
+    assert_eq!(late.wait().await, now + Duration::from_millis(10));
+}
+
+#[test]
+fn test_clock_bus_too_many_subscribers() {
+    let bus = TestBus::new();
+    let first = bus.subscriber();
+    let second = bus.subscriber();
+    assert!(first.is_some() && second.is_some());
+
+    assert!(bus.subscriber().is_none());
+
+    // a slot is freed when a subscriber is dropped
+    drop(first);
+    assert!(bus.subscriber().is_some());
+}
@@ -0,0 +1,145 @@
+use embassy_time::{Duration, Instant};
+
+use dg_clock::OverlapPolicy;
+use dg_types::FloatParameter;
+
+mod common;
+
+use common::{MockClockOut, Pulse, run_for};
+
+/// BPM parameter linearly swept over time.
+struct Sweep {
+    start: Instant,
+    from: f32,
+    to: f32,
+    over: Duration,
+}
+
+impl Sweep {
+    fn new(from: f32, to: f32, over: Duration) -> Self {
+        Self {
+            start: Instant::now(),
+            from,
+            to,
+            over,
+        }
+    }
+}
+
+impl FloatParameter for Sweep {
+    async fn get(&mut self) -> f32 {
+        let t = self.start.elapsed().as_micros() as f32 / self.over.as_micros() as f32;
+        self.from + (self.to - self.from) * t.min(1.0)
+    }
+}
+
+/// BPM parameter which slightly changes on every read, like a noisy pot.
+struct Jitter {
+    bpm: f32,
+    count: u32,
+}
+
+impl FloatParameter for Jitter {
+    async fn get(&mut self) -> f32 {
+        self.count += 1;
+        self.bpm + (self.count % 2) as f32
+    }
+}
+
+fn intervals_ms(pulses: &[Pulse]) -> Vec<f32> {
+    pulses
+        .windows(2)
+        .map(|w| (w[1].time() - w[0].time()).as_micros() as f32 / 1000.0)
+        .collect()
+}
+
+#[tokio::test]
+async fn test_clock_noisy_bpm_keeps_period() {
+    let mut pulses = Vec::new();
+
+    run_for(
+        dg_clock::clock(
+            MockClockOut::new(&mut pulses),
+            Jitter {
+                bpm: 3000.0,
+                count: 0,
+            },
+            OverlapPolicy::Drop,
+        ),
+        Duration::from_millis(110),
+    )
+    .await;
+
+    assert_eq!(pulses.len(), 5, "{pulses:?}");
+    for interval in intervals_ms(&pulses) {
+        assert!((17.0..23.0).contains(&interval), "{interval}ms");
+    }
+}
+
+#[tokio::test]
+async fn test_clock_sweep_up() {
+    let start = Instant::now();
+    let mut pulses = Vec::new();
+
+    // 50Hz -> 100Hz over 200ms: the n-th pulse is due when 50t + 125t² = n
+    run_for(
+        dg_clock::clock(
+            MockClockOut::new(&mut pulses),
+            Sweep::new(3000.0, 6000.0, Duration::from_millis(200)),
+            OverlapPolicy::Drop,
+        ),
+        Duration::from_millis(185),
+    )
+    .await;
+
+    assert_eq!(pulses.len(), 13, "{pulses:?}");
+
+    for (n, pulse) in pulses.iter().enumerate() {
+        let n = (n + 1) as f32;
+        let expected = (-50.0 + (2500.0 + 500.0 * n).sqrt()) / 250.0;
+        let actual = (pulse.time() - start).as_micros() as f32 / 1_000_000.0;
+        assert!(
+            (actual - expected).abs() < 0.005,
+            "pulse {n} at {actual}s, expected {expected}s"
+        );
+    }
+
+    // no double or missing pulses: intervals only shrink
+    let intervals = intervals_ms(&pulses);
+    assert!(
+        intervals.iter().all(|i| (9.0..22.0).contains(i)),
+        "{intervals:?}"
+    );
+    assert!(
+        intervals.windows(2).all(|w| w[1] < w[0] + 2.0),
+        "{intervals:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_clock_sweep_down() {
+    let mut pulses = Vec::new();
+
+    run_for(
+        dg_clock::clock(
+            MockClockOut::new(&mut pulses),
+            Sweep::new(6000.0, 3000.0, Duration::from_millis(200)),
+            OverlapPolicy::Drop,
+        ),
+        Duration::from_millis(250),
+    )
+    .await;
+
+    // 15 pulses during the sweep, then 2.5 more at 50Hz
+    assert!((16..=18).contains(&pulses.len()), "{pulses:?}");
+
+    let intervals = intervals_ms(&pulses);
+    assert!(
+        intervals.iter().all(|i| (9.0..22.0).contains(i)),
+        "{intervals:?}"
+    );
+    assert!(
+        intervals.windows(2).all(|w| w[1] > w[0] - 2.0),
+        "{intervals:?}"
+    );
+}
@@ -1,104 +1,14 @@
-use std::cmp::Reverse;
-use std::collections::BinaryHeap;
-use std::pin::pin;
-
-use embassy_futures::select::{Either, select};
 use embassy_time::{Duration, Instant, Timer};
 
-use dg_types::{ClockIn, ClockOut};
+use dg_clock::OverlapPolicy;
+use dg_types::ClockIn;
 
-#[derive(Debug, Clone)]
-pub struct Pulse {
-    time: Instant,
-    duration: Duration,
-}
+mod common;
 
-impl Pulse {
-    pub fn new(time: Instant, duration: Duration) -> Self {
-        Self { time, duration }
-    }
-
-    pub fn time(&self) -> Instant {
-        self.time
-    }
-
-    pub fn duration(&self) -> Duration {
-        self.duration
-    }
-
-    pub fn assert_shortly_after(&self, other: Instant) {
-        assert!(self.time >= other, "{} is not after {}", self.time, other);
-        assert!(
-            self.time <= other + Duration::from_millis(3),
-            "{} is not before {}",
-            self.time,
-            other + Duration::from_millis(3)
-        );
-    }
-}
-
-impl From<(Instant, Duration)> for Pulse {
-    fn from((time, duration): (Instant, Duration)) -> Self {
-        Self::new(time, duration)
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct MockClockIn {
-    events: BinaryHeap<Reverse<Instant>>,
-}
-
-impl MockClockIn {
-    pub fn new(events: impl IntoIterator<Item = Instant>) -> Self {
-        MockClockIn {
-            events: events.into_iter().map(Reverse).collect(),
-        }
-    }
-
-    pub fn is_empty(&self) -> bool {
-        self.events.is_empty()
-    }
-}
-
-impl ClockIn for MockClockIn {
-    async fn wait(&mut self) -> Instant {
-        let now = Instant::now();
-        while let Some(Reverse(next_event)) = self.events.peek() {
-            if *next_event <= now {
-                self.events.pop();
-            } else {
-                break;
-            }
-        }
-
-        if let Some(next_event) = self.events.pop() {
-            Timer::at(next_event.0).await;
-            next_event.0
-        } else {
-            // wait forever if no events are left
-            std::future::pending().await
-        }
-    }
-}
-
-#[derive(Debug)]
-pub struct MockClockOut<'a> {
-    pulses: &'a mut Vec<Pulse>,
-}
-
-impl<'a> MockClockOut<'a> {
-    pub fn new(pulses: &'a mut Vec<Pulse>) -> Self {
-        Self { pulses }
-    }
-}
-
-impl ClockOut for MockClockOut<'_> {
-    async fn emit_pulse(&mut self, duration: Duration) {
-        let now = Instant::now();
-        self.pulses.push(Pulse::new(now, duration));
-        Timer::after(duration).await;
-    }
-}
+use common::{
+    MockClockIn, MockClockOut, MockGateOut, Pulse, assert_pulses, gate_pulses, millis, run_for,
+    simulate,
+};
 
 #[tokio::test]
 async fn test_mock_clock_in() {
@@ -109,9 +19,12 @@ async fn test_mock_clock_in() {
         now + Duration::from_millis(30),
     ]);
 
-    assert_eq!(clock_in.wait().await, now + Duration::from_millis(10));
-    assert_eq!(clock_in.wait().await, now + Duration::from_millis(20));
-    assert_eq!(clock_in.wait().await, now + Duration::from_millis(30));
+    simulate(async {
+        assert_eq!(clock_in.wait().await, now + Duration::from_millis(10));
+        assert_eq!(clock_in.wait().await, now + Duration::from_millis(20));
+        assert_eq!(clock_in.wait().await, now + Duration::from_millis(30));
+    })
+    .await;
 }
 
 #[tokio::test]
@@ -123,9 +36,12 @@ async fn test_mock_clock_in_drops_past_event() {
         now + Duration::from_millis(30),
     ]);
 
-    assert_eq!(clock_in.wait().await, now + Duration::from_millis(10));
-    Timer::after(Duration::from_millis(15)).await;
-    assert_eq!(clock_in.wait().await, now + Duration::from_millis(30));
+    simulate(async {
+        assert_eq!(clock_in.wait().await, now + Duration::from_millis(10));
+        Timer::after(Duration::from_millis(15)).await;
+        assert_eq!(clock_in.wait().await, now + Duration::from_millis(30));
+    })
+    .await;
     assert!(clock_in.is_empty());
 }
 
@@ -134,34 +50,26 @@ async fn test_clock_forward() {
     let now = Instant::now();
     let mut pulses = Vec::new();
 
-    {
-        let mut clock_forward_mut = pin!(dg_clock::clock_forward(
+    run_for(
+        dg_clock::clock_forward(
             MockClockIn::new([
                 now + Duration::from_millis(10),
                 now + Duration::from_millis(20),
             ]),
             MockClockOut::new(&mut pulses),
             Duration::from_millis(5),
-        ));
-
-        let mut end_fut = pin!(async {
-            Timer::after(Duration::from_millis(50)).await;
-        });
-
-        loop {
-            match select(&mut clock_forward_mut, &mut end_fut).await {
-                Either::First(_) => {}
-                Either::Second(_) => break,
-            }
-        }
-    }
+            OverlapPolicy::Drop,
+        ),
+        Duration::from_millis(50),
+    )
+    .await;
 
     assert_eq!(pulses.len(), 2);
     pulses[0].assert_shortly_after(now + Duration::from_millis(10));
-    assert_eq!(pulses[0].duration, Duration::from_millis(5));
+    assert_eq!(pulses[0].duration(), Duration::from_millis(5));
 
     pulses[1].assert_shortly_after(now + Duration::from_millis(20));
-    assert_eq!(pulses[1].duration, Duration::from_millis(5));
+    assert_eq!(pulses[1].duration(), Duration::from_millis(5));
 }
 
 #[tokio::test]
@@ -169,8 +77,8 @@ async fn test_clock_forward_drops_pulse() {
     let now = Instant::now();
     let mut pulses = Vec::new();
 
-    {
-        let mut clock_forward_mut = pin!(dg_clock::clock_forward(
+    run_for(
+        dg_clock::clock_forward(
             MockClockIn::new([
                 now + Duration::from_millis(10),
                 now + Duration::from_millis(20),
@@ -178,24 +86,98 @@ async fn test_clock_forward_drops_pulse() {
             ]),
             MockClockOut::new(&mut pulses),
             Duration::from_millis(15),
-        ));
-
-        let mut end_fut = pin!(async {
-            Timer::after(Duration::from_millis(50)).await;
-        });
-
-        loop {
-            match select(&mut clock_forward_mut, &mut end_fut).await {
-                Either::First(_) => {}
-                Either::Second(_) => break,
-            }
-        }
-    }
+            OverlapPolicy::Drop,
+        ),
+        Duration::from_millis(50),
+    )
+    .await;
 
     assert_eq!(pulses.len(), 2);
     pulses[0].assert_shortly_after(now + Duration::from_millis(10));
-    assert_eq!(pulses[0].duration, Duration::from_millis(15));
+    assert_eq!(pulses[0].duration(), Duration::from_millis(15));
 
     pulses[1].assert_shortly_after(now + Duration::from_millis(30));
-    assert_eq!(pulses[1].duration, Duration::from_millis(15));
+    assert_eq!(pulses[1].duration(), Duration::from_millis(15));
+}
+
+async fn run_overlapping(policy: OverlapPolicy) -> (Instant, Vec<Pulse>) {
+    let now = Instant::now();
+    let mut edges = Vec::new();
+
+    run_for(
+        dg_clock::clock_forward(
+            MockClockIn::new(millis(now, &[10, 20, 30])),
+            MockGateOut::new(&mut edges),
+            Duration::from_millis(15),
+            policy,
+        ),
+        Duration::from_millis(65),
+    )
+    .await;
+
+    (now, gate_pulses(&edges))
+}
+
+#[tokio::test]
+async fn test_clock_forward_retrigger() {
+    let (now, pulses) = run_overlapping(OverlapPolicy::Retrigger).await;
+
+    assert_eq!(pulses.len(), 3);
+    pulses[0].assert_shortly_after(now + Duration::from_millis(10));
+    pulses[0].assert_lasted_about(Duration::from_millis(9));
+
+    pulses[1].assert_shortly_after(now + Duration::from_millis(20));
+    pulses[1].assert_lasted_about(Duration::from_millis(8));
+
+    pulses[2].assert_shortly_after(now + Duration::from_millis(30));
+    pulses[2].assert_lasted_about(Duration::from_millis(15));
+}
+
+#[tokio::test]
+async fn test_clock_forward_extend() {
+    let (now, pulses) = run_overlapping(OverlapPolicy::Extend).await;
+
+    assert_eq!(pulses.len(), 1);
+    pulses[0].assert_shortly_after(now + Duration::from_millis(10));
+    pulses[0].assert_lasted_about(Duration::from_millis(35));
+}
+
+#[tokio::test]
+async fn test_clock_forward_queue() {
+    let (now, pulses) = run_overlapping(OverlapPolicy::Queue).await;
+
+    assert_eq!(pulses.len(), 3);
+    pulses[0].assert_shortly_after(now + Duration::from_millis(10));
+    pulses[1].assert_shortly_after(now + Duration::from_millis(26));
+    pulses[2].assert_shortly_after(now + Duration::from_millis(42));
+
+    for pulse in &pulses {
+        pulse.assert_lasted_about(Duration::from_millis(15));
+    }
+}
+
+#[tokio::test]
+async fn test_clock_forward_overlap_without_level() {
+    // outputs without a level only see whole pulses: a retriggered pulse is a new pulse, and an
+    // extended one isn't emitted again
+    for (policy, expected_ms) in [
+        (OverlapPolicy::Retrigger, [10, 20, 30].as_slice()),
+        (OverlapPolicy::Extend, &[10]),
+    ] {
+        let now = Instant::now();
+        let mut pulses = Vec::new();
+
+        run_for(
+            dg_clock::clock_forward(
+                MockClockIn::new(millis(now, &[10, 20, 30])),
+                MockClockOut::new(&mut pulses),
+                Duration::from_millis(15),
+                policy,
+            ),
+            Duration::from_millis(65),
+        )
+        .await;
+
+        assert_pulses(now, &pulses, expected_ms);
+    }
 }
@@ -0,0 +1,53 @@
+use embassy_time::{Duration, Instant};
+
+use dg_clock::{OverlapPolicy, SyncMode};
+
+mod common;
+
+use common::{MockClockIn, MockClockOut, Pulse, assert_pulses, millis, run_for};
+
+async fn run_synced(sync: &[u64], mode: SyncMode, duration_ms: u64) -> (Instant, Vec<Pulse>) {
+    let now = Instant::now();
+    let mut pulses = Vec::new();
+
+    // 3000 BPM -> 20ms period
+    run_for(
+        dg_clock::clock_synced(
+            MockClockOut::new(&mut pulses),
+            3000.0,
+            MockClockIn::new(millis(now, sync)),
+            mode,
+            OverlapPolicy::Drop,
+        ),
+        Duration::from_millis(duration_ms),
+    )
+    .await;
+
+    (now, pulses)
+}
+
+#[tokio::test]
+async fn test_clock_free_running() {
+    let (now, pulses) = run_synced(&[], SyncMode::Hard, 95).await;
+    assert_pulses(now, &pulses, &[20, 40, 60, 80]);
+}
+
+#[tokio::test]
+async fn test_clock_hard_sync() {
+    let (now, pulses) = run_synced(&[50], SyncMode::Hard, 95).await;
+    assert_pulses(now, &pulses, &[20, 40, 50, 70, 90]);
+}
+
+#[tokio::test]
+async fn test_clock_soft_sync_early() {
+    // sync edge 8ms after our tick: next tick is pushed back by 4ms
+    let (now, pulses) = run_synced(&[48], SyncMode::Soft { strength: 0.5 }, 95).await;
+    assert_pulses(now, &pulses, &[20, 40, 64, 84]);
+}
+
+#[tokio::test]
+async fn test_clock_soft_sync_late() {
+    // sync edge 6ms before our tick: next tick is brought forward by 3ms
+    let (now, pulses) = run_synced(&[34], SyncMode::Soft { strength: 0.5 }, 95).await;
+    assert_pulses(now, &pulses, &[20, 37, 57, 77]);
+}
@@ -0,0 +1,82 @@
+use embassy_time::{Duration, Instant};
+
+use dg_clock::{OverlapPolicy, TrainMode};
+
+mod common;
+
+use common::{MockClockIn, MockGateOut, gate_pulses, millis, run_for};
+
+async fn assert_train(policy: OverlapPolicy, expected_ms: &[u64]) {
+    let now = Instant::now();
+    let mut edges = Vec::new();
+
+    // 3 pulses at 6000 BPM -> 10ms period, 5ms pulses
+    run_for(
+        dg_clock::clock_train(
+            MockClockIn::new(millis(now, &[10, 27])),
+            MockGateOut::new(&mut edges),
+            3,
+            6000.0,
+            0.0,
+            TrainMode::Fixed(policy),
+        ),
+        Duration::from_millis(70),
+    )
+    .await;
+
+    let pulses = gate_pulses(&edges);
+    assert_eq!(pulses.len(), expected_ms.len(), "{pulses:?}");
+    for (pulse, ms) in pulses.iter().zip(expected_ms) {
+        pulse.assert_shortly_after(now + Duration::from_millis(*ms));
+        pulse.assert_lasted_about(Duration::from_millis(5));
+    }
+}
+
+#[tokio::test]
+async fn test_clock_train_drop() {
+    assert_train(OverlapPolicy::Drop, &[10, 20, 30]).await;
+}
+
+#[tokio::test]
+async fn test_clock_train_retrigger() {
+    assert_train(OverlapPolicy::Retrigger, &[10, 20, 27, 37, 47]).await;
+}
+
+#[tokio::test]
+async fn test_clock_train_extend() {
+    assert_train(OverlapPolicy::Extend, &[10, 20, 30, 40, 50]).await;
+}
+
+#[tokio::test]
+async fn test_clock_train_queue() {
+    assert_train(OverlapPolicy::Queue, &[10, 20, 30, 40, 50, 60]).await;
+}
+
+#[tokio::test]
+async fn test_clock_train_no_drift() {
+    let now = Instant::now();
+    let mut edges = Vec::new();
+
+    // 1000 pulses at 12000 BPM -> 5ms period
+    run_for(
+        dg_clock::clock_train(
+            MockClockIn::new(millis(now, &[10])),
+            MockGateOut::new(&mut edges),
+            1000,
+            12000.0,
+            0.0,
+            TrainMode::Fixed(OverlapPolicy::Queue),
+        ),
+        Duration::from_millis(10 + 1000 * 5 + 10),
+    )
+    .await;
+
+    let pulses = gate_pulses(&edges);
+    assert_eq!(pulses.len(), 1000);
+
+    // every pulse lands exactly on the grid anchored to the clock
+    for (index, pulse) in pulses.iter().enumerate() {
+        let deadline = now + Duration::from_millis(10 + 5 * index as u64);
+        assert_eq!(pulse.time(), deadline, "pulse {index} off the grid");
+    }
+}
@@ -0,0 +1,411 @@
+#![allow(dead_code)]
+
+use std::cell::{Cell, RefCell};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::pin::pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll, Wake, Waker};
+
+use embassy_time::{Duration, Instant, Timer};
+use embassy_time_driver::Driver;
+
+use dg_types::{ClockIn, ClockOut, CvIn, CvOut, GateIn, GateOut};
+
+#[derive(Debug, Clone)]
+pub struct Pulse {
+    time: Instant,
+    duration: Duration,
+}
+
+impl Pulse {
+    pub fn new(time: Instant, duration: Duration) -> Self {
+        Self { time, duration }
+    }
+
+    pub fn time(&self) -> Instant {
+        self.time
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn assert_shortly_after(&self, other: Instant) {
+        assert_instant_shortly_after(self.time, other);
+    }
+
+    pub fn end(&self) -> Instant {
+        self.time + self.duration
+    }
+
+    pub fn assert_ended_shortly_after(&self, other: Instant) {
+        assert_instant_shortly_after(self.end(), other);
+    }
+
+    pub fn assert_lasted_about(&self, duration: Duration) {
+        assert!(
+            self.duration >= duration,
+            "{} is shorter than {}",
+            self.duration,
+            duration
+        );
+        assert!(
+            self.duration <= duration + Duration::from_millis(3),
+            "{} is longer than {}",
+            self.duration,
+            duration + Duration::from_millis(3)
+        );
+    }
+}
+
+impl From<(Instant, Duration)> for Pulse {
+    fn from((time, duration): (Instant, Duration)) -> Self {
+        Self::new(time, duration)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MockClockIn {
+    events: BinaryHeap<Reverse<Instant>>,
+}
+
+impl MockClockIn {
+    pub fn new(events: impl IntoIterator<Item = Instant>) -> Self {
+        MockClockIn {
+            events: events.into_iter().map(Reverse).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+impl ClockIn for MockClockIn {
+    async fn wait(&mut self) -> Instant {
+        let now = Instant::now();
+        while let Some(Reverse(next_event)) = self.events.peek() {
+            if *next_event <= now {
+                self.events.pop();
+            } else {
+                break;
+            }
+        }
+
+        // only pop the event once it happened, so that a cancelled wait doesn't lose it
+        if let Some(Reverse(next_event)) = self.events.peek().copied() {
+            Timer::at(next_event).await;
+            self.events.pop();
+            next_event
+        } else {
+            // wait forever if no events are left
+            std::future::pending().await
+        }
+    }
+}
+
+/// Gate input following a scripted list of level changes, low before the first one.
+#[derive(Debug, Clone)]
+pub struct MockGateIn {
+    changes: Vec<(Instant, bool)>,
+}
+
+impl MockGateIn {
+    pub fn new(changes: impl IntoIterator<Item = (Instant, bool)>) -> Self {
+        let mut changes = changes.into_iter().collect::<Vec<_>>();
+        changes.sort_by_key(|(at, _)| *at);
+        Self { changes }
+    }
+
+    pub fn level_at(&self, at: Instant) -> bool {
+        self.changes
+            .iter()
+            .rev()
+            .find(|(change, _)| *change <= at)
+            .is_some_and(|(_, level)| *level)
+    }
+
+    async fn wait_for(&self, level: bool) {
+        loop {
+            let now = Instant::now();
+            if self.level_at(now) == level {
+                return;
+            }
+
+            match self.changes.iter().find(|(at, _)| *at > now) {
+                Some((at, _)) => Timer::at(*at).await,
+                None => std::future::pending().await,
+            }
+        }
+    }
+}
+
+impl GateIn for MockGateIn {
+    async fn wait_for_high(&mut self) {
+        self.wait_for(true).await;
+    }
+
+    async fn wait_for_low(&mut self) {
+        self.wait_for(false).await;
+    }
+}
+
+#[derive(Debug)]
+pub struct MockClockOut<'a> {
+    pulses: &'a mut Vec<Pulse>,
+}
+
+impl<'a> MockClockOut<'a> {
+    pub fn new(pulses: &'a mut Vec<Pulse>) -> Self {
+        Self { pulses }
+    }
+}
+
+impl ClockOut for MockClockOut<'_> {
+    async fn emit_pulse(&mut self, duration: Duration) {
+        let now = Instant::now();
+        self.pulses.push(Pulse::new(now, duration));
+        Timer::after(duration).await;
+    }
+}
+
+/// Records every level change of a gate output.
+#[derive(Debug)]
+pub struct MockGateOut<'a> {
+    edges: &'a mut Vec<(Instant, bool)>,
+}
+
+impl<'a> MockGateOut<'a> {
+    pub fn new(edges: &'a mut Vec<(Instant, bool)>) -> Self {
+        Self { edges }
+    }
+}
+
+impl ClockOut for MockGateOut<'_> {
+    async fn emit_pulse(&mut self, duration: Duration) {
+        self.set_high().await;
+        Timer::after(duration).await;
+        self.set_low().await;
+    }
+
+    async fn end_pulse(&mut self) {
+        self.set_low().await;
+    }
+}
+
+impl GateOut for MockGateOut<'_> {
+    async fn set_high(&mut self) {
+        self.edges.push((Instant::now(), true));
+    }
+
+    async fn set_low(&mut self) {
+        self.edges.push((Instant::now(), false));
+    }
+}
+
+/// CV input whose value is a function of the time at which it is read.
+pub struct MockCvIn<F: FnMut(Instant) -> u16> {
+    value_at: F,
+}
+
+impl<F: FnMut(Instant) -> u16> MockCvIn<F> {
+    pub fn new(value_at: F) -> Self {
+        Self { value_at }
+    }
+}
+
+impl<F: FnMut(Instant) -> u16> CvIn for MockCvIn<F> {
+    async fn get_value(&mut self) -> u16 {
+        (self.value_at)(Instant::now())
+    }
+}
+
+/// Records every value written to a CV output.
+#[derive(Debug)]
+pub struct MockCvOut<'a> {
+    values: &'a mut Vec<(Instant, u16)>,
+}
+
+impl<'a> MockCvOut<'a> {
+    pub fn new(values: &'a mut Vec<(Instant, u16)>) -> Self {
+        Self { values }
+    }
+}
+
+impl CvOut for MockCvOut<'_> {
+    async fn set_value(&mut self, value: u16) {
+        self.values.push((Instant::now(), value));
+    }
+}
+
+/// Last value written to a [`MockCvOut`] at or before `at`, 0 if none.
+pub fn cv_value_at(values: &[(Instant, u16)], at: Instant) -> u16 {
+    values
+        .iter()
+        .rev()
+        .find(|(time, _)| *time <= at)
+        .map_or(0, |(_, value)| *value)
+}
+
+/// Rebuilds the pulses from the level changes recorded by a [`MockGateOut`].
+pub fn gate_pulses(edges: &[(Instant, bool)]) -> Vec<Pulse> {
+    let mut pulses = Vec::new();
+    let mut high_since = None;
+
+    for &(time, high) in edges {
+        match (high, high_since) {
+            (true, None) => high_since = Some(time),
+            (false, Some(start)) => {
+                pulses.push(Pulse::new(start, time - start));
+                high_since = None;
+            }
+            _ => {}
+        }
+    }
+
+    pulses
+}
+
+/// Instants at the given offsets (in milliseconds) from `now`.
+pub fn millis(now: Instant, offsets: &[u64]) -> Vec<Instant> {
+    offsets
+        .iter()
+        .map(|ms| now + Duration::from_millis(*ms))
+        .collect()
+}
+
+// Tests run on a virtual clock so that timing assertions don't depend on the host scheduler. The
+// clock is thread-local because the test harness runs tests of the same binary in parallel, each
+// on its own thread.
+thread_local! {
+    static NOW: Cell<u64> = const { Cell::new(0) };
+    static ALARMS: RefCell<Vec<(u64, Waker)>> = const { RefCell::new(Vec::new()) };
+    static DRIVING: Cell<bool> = const { Cell::new(false) };
+}
+
+struct VirtualDriver;
+
+impl Driver for VirtualDriver {
+    fn now(&self) -> u64 {
+        NOW.get()
+    }
+
+    fn schedule_wake(&self, at: u64, waker: &Waker) {
+        // nothing else would ever advance the clock and wake the timer
+        assert!(
+            DRIVING.get(),
+            "timers must be awaited within `run_for` or `simulate`"
+        );
+
+        ALARMS.with_borrow_mut(|alarms| {
+            if !alarms.iter().any(|(t, w)| *t == at && w.will_wake(waker)) {
+                alarms.push((at, waker.clone()));
+            }
+        });
+    }
+}
+
+embassy_time_driver::time_driver_impl!(static DRIVER: VirtualDriver = VirtualDriver);
+
+struct WakeFlag(AtomicBool);
+
+impl Wake for WakeFlag {
+    fn wake(self: Arc<Self>) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Polls `fut` until it completes, advancing the virtual clock whenever it is blocked on a timer.
+/// Gives up and returns `None` once the clock would pass `deadline`.
+fn drive<F: Future>(fut: F, deadline: Option<Instant>) -> Option<F::Output> {
+    let mut fut = pin!(fut);
+    let flag = Arc::new(WakeFlag(AtomicBool::new(true)));
+    let waker = Waker::from(flag.clone());
+    let mut cx = Context::from_waker(&waker);
+    let mut polls_without_progress = 0;
+
+    loop {
+        if flag.0.swap(false, Ordering::SeqCst) {
+            let driving = DRIVING.replace(true);
+            let poll = fut.as_mut().poll(&mut cx);
+            DRIVING.set(driving);
+
+            if let Poll::Ready(output) = poll {
+                return Some(output);
+            }
+
+            polls_without_progress += 1;
+            assert!(
+                polls_without_progress < 100_000,
+                "future keeps waking itself without letting time advance"
+            );
+            continue;
+        }
+
+        let next_alarm = ALARMS.with_borrow(|alarms| alarms.iter().map(|(at, _)| *at).min());
+        let deadline = deadline.map(|deadline| deadline.as_ticks());
+        let at = match (next_alarm, deadline) {
+            (Some(at), Some(deadline)) if at > deadline => {
+                NOW.set(NOW.get().max(deadline));
+                return None;
+            }
+            (Some(at), _) => at,
+            (None, Some(deadline)) => {
+                NOW.set(NOW.get().max(deadline));
+                return None;
+            }
+            (None, None) => panic!("future is blocked and no timer is pending"),
+        };
+
+        let now = NOW.get().max(at);
+        NOW.set(now);
+        polls_without_progress = 0;
+
+        let due: Vec<_> = ALARMS.with_borrow_mut(|alarms| {
+            let (due, pending) = alarms.drain(..).partition(|(at, _)| *at <= now);
+            *alarms = pending;
+            due
+        });
+        due.into_iter().for_each(|(_, waker)| waker.wake());
+    }
+}
+
+/// Asserts that each pulse started shortly after the matching offset (in milliseconds) from `now`.
+pub fn assert_pulses(now: Instant, pulses: &[Pulse], expected_ms: &[u64]) {
+    assert_eq!(pulses.len(), expected_ms.len(), "{pulses:?}");
+    for (pulse, ms) in pulses.iter().zip(expected_ms) {
+        pulse.assert_shortly_after(now + Duration::from_millis(*ms));
+    }
+}
+
+/// Asserts that `actual` is at or shortly after `expected`.
+pub fn assert_instant_shortly_after(actual: Instant, expected: Instant) {
+    assert!(actual >= expected, "{actual} is not after {expected}");
+    assert!(
+        actual <= expected + Duration::from_millis(3),
+        "{actual} is not before {}",
+        expected + Duration::from_millis(3)
+    );
+}
+
+/// Asserts that each pulse started and ended shortly after the matching pair of offsets (in
+/// milliseconds) from `now`.
+pub fn assert_gates(now: Instant, pulses: &[Pulse], expected_ms: &[(u64, u64)]) {
+    assert_eq!(pulses.len(), expected_ms.len(), "{pulses:?}");
+    for (pulse, (start, end)) in pulses.iter().zip(expected_ms) {
+        pulse.assert_shortly_after(now + Duration::from_millis(*start));
+        pulse.assert_ended_shortly_after(now + Duration::from_millis(*end));
+    }
+}
+
+/// Drives `fut` until `duration` has elapsed.
+pub async fn run_for(fut: impl Future, duration: Duration) {
+    drive(fut, Some(Instant::now() + duration));
+}
+
+/// Drives `fut` to completion.
+pub async fn simulate<F: Future>(fut: F) -> F::Output {
+    drive(fut, None).expect("no deadline was set")
+}
@@ -0,0 +1,114 @@
+use embassy_time::{Duration, Instant};
+
+use dg_clock::{GateEdge, GateLengthMode, OverlapPolicy};
+
+mod common;
+
+use common::{
+    MockClockIn, MockGateIn, MockGateOut, Pulse, assert_gates, gate_pulses, millis, run_for,
+};
+
+async fn run_gate_to_trigger(edges: GateEdge) -> (Instant, Vec<Pulse>) {
+    let now = Instant::now();
+    let ms = |ms| now + Duration::from_millis(ms);
+    let mut out = Vec::new();
+
+    run_for(
+        dg_clock::gate_to_trigger(
+            MockGateIn::new([
+                (ms(10), true),
+                (ms(30), false),
+                (ms(40), true),
+                (ms(42), false),
+            ]),
+            MockGateOut::new(&mut out),
+            edges,
+            Duration::from_millis(4),
+            OverlapPolicy::Queue,
+        ),
+        Duration::from_millis(60),
+    )
+    .await;
+
+    (now, gate_pulses(&out))
+}
+
+async fn run_trigger_to_gate(length: f32, mode: GateLengthMode) -> (Instant, Vec<Pulse>) {
+    let now = Instant::now();
+    let mut out = Vec::new();
+
+    run_for(
+        dg_clock::trigger_to_gate(
+            MockClockIn::new(millis(now, &[10, 30, 50])),
+            MockGateOut::new(&mut out),
+            length,
+            mode,
+            OverlapPolicy::Retrigger,
+        ),
+        Duration::from_millis(70),
+    )
+    .await;
+
+    (now, gate_pulses(&out))
+}
+
+#[tokio::test]
+async fn test_gate_to_trigger_rising() {
+    let (now, pulses) = run_gate_to_trigger(GateEdge::Rising).await;
+    assert_gates(now, &pulses, &[(10, 14), (40, 44)]);
+}
+
+#[tokio::test]
+async fn test_gate_to_trigger_falling() {
+    let (now, pulses) = run_gate_to_trigger(GateEdge::Falling).await;
+    assert_gates(now, &pulses, &[(30, 34), (42, 46)]);
+}
+
+#[tokio::test]
+async fn test_gate_to_trigger_both() {
+    // the falling edge of the short gate is queued after the rising edge trigger
+    let (now, pulses) = run_gate_to_trigger(GateEdge::Both).await;
+    assert_gates(now, &pulses, &[(10, 14), (30, 34), (40, 44), (45, 49)]);
+}
+
+#[tokio::test]
+async fn test_gate_to_trigger_starts_high() {
+    let now = Instant::now();
+    let ms = |ms| now + Duration::from_millis(ms);
+    let mut out = Vec::new();
+
+    // the initial level isn't an edge
+    run_for(
+        dg_clock::gate_to_trigger(
+            MockGateIn::new([(now, true), (ms(20), false), (ms(40), true)]),
+            MockGateOut::new(&mut out),
+            GateEdge::Both,
+            Duration::from_millis(4),
+            OverlapPolicy::Queue,
+        ),
+        Duration::from_millis(60),
+    )
+    .await;
+
+    assert_gates(now, &gate_pulses(&out), &[(20, 24), (40, 44)]);
+}
+
+#[tokio::test]
+async fn test_trigger_to_gate_absolute() {
+    let (now, pulses) = run_trigger_to_gate(12.0, GateLengthMode::Absolute).await;
+    assert_gates(now, &pulses, &[(10, 22), (30, 42), (50, 62)]);
+}
+
+#[tokio::test]
+async fn test_trigger_to_gate_period_fraction() {
+    // the first trigger is dropped as the period isn't known yet
+    let (now, pulses) = run_trigger_to_gate(0.75, GateLengthMode::PeriodFraction).await;
+    assert_gates(now, &pulses, &[(30, 45), (50, 65)]);
+}
+
+#[tokio::test]
+async fn test_trigger_to_gate_retrigger() {
+    // gates longer than the period are retriggered, the last one is still high at the end
+    let (now, pulses) = run_trigger_to_gate(25.0, GateLengthMode::Absolute).await;
+    assert_gates(now, &pulses, &[(10, 30), (31, 50)]);
+}
@@ -0,0 +1,56 @@
+use embassy_time::{Duration, Instant};
+
+use dg_clock::DelayMode;
+
+mod common;
+
+use common::{MockClockIn, MockClockOut, Pulse, assert_pulses, millis, run_for};
+
+async fn run_delay(events: &[u64], delay: f32, mode: DelayMode) -> (Instant, Vec<Pulse>) {
+    let now = Instant::now();
+    let mut pulses = Vec::new();
+
+    run_for(
+        dg_clock::clock_delay(
+            MockClockIn::new(millis(now, events)),
+            MockClockOut::new(&mut pulses),
+            delay,
+            mode,
+            Duration::from_millis(2),
+        ),
+        Duration::from_millis(80),
+    )
+    .await;
+
+    for pulse in &pulses {
+        assert_eq!(pulse.duration(), Duration::from_millis(2));
+    }
+
+    (now, pulses)
+}
+
+#[tokio::test]
+async fn test_delay_absolute() {
+    let (now, pulses) = run_delay(&[10, 30, 50], 5.0, DelayMode::Absolute).await;
+    assert_pulses(now, &pulses, &[15, 35, 55]);
+}
+
+#[tokio::test]
+async fn test_delay_absolute_longer_than_period() {
+    let (now, pulses) = run_delay(&[10, 20, 30, 40], 25.0, DelayMode::Absolute).await;
+    assert_pulses(now, &pulses, &[35, 45, 55, 65]);
+}
+
+#[tokio::test]
+async fn test_delay_period_fraction() {
+    let (now, pulses) = run_delay(&[10, 30, 50], 0.25, DelayMode::PeriodFraction).await;
+
+    // the first pulse is dropped, as the period isn't known yet
+    assert_pulses(now, &pulses, &[35, 55]);
+}
+
+#[tokio::test]
+async fn test_delay_period_fraction_longer_than_period() {
+    let (now, pulses) = run_delay(&[10, 20, 30, 40], 2.5, DelayMode::PeriodFraction).await;
+    assert_pulses(now, &pulses, &[45, 55, 65]);
+}
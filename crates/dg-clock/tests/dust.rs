@@ -0,0 +1,146 @@
+use embassy_time::{Duration, Instant};
+
+use dg_clock::{Dust, OverlapPolicy};
+
+mod common;
+
+use common::{MockGateOut, assert_pulses, gate_pulses, run_for};
+
+/// Mean and standard deviation of `values`.
+fn stats(values: &[f32]) -> (f32, f32) {
+    let mean = values.iter().sum::<f32>() / values.len() as f32;
+    let variance = values
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f32>()
+        / values.len() as f32;
+    (mean, variance.sqrt())
+}
+
+fn spacings(seed: u64, regularity: f32, count: usize) -> Vec<f32> {
+    let mut dust = Dust::from_seed(seed);
+    (0..count).map(|_| dust.next_spacing(regularity)).collect()
+}
+
+#[test]
+fn test_dust_poisson_spacing() {
+    let spacings = spacings(1, 0.0, 20000);
+    let (mean, deviation) = stats(&spacings);
+    assert!((mean - 1.0).abs() < 0.03, "mean {mean}");
+    assert!((deviation - 1.0).abs() < 0.05, "deviation {deviation}");
+
+    // exponential distribution: P(X < 1) = 1 - 1/e
+    let below_mean = spacings.iter().filter(|spacing| **spacing < 1.0).count();
+    let ratio = below_mean as f32 / spacings.len() as f32;
+    assert!((ratio - 0.632).abs() < 0.02, "ratio {ratio}");
+    assert!(
+        spacings
+            .iter()
+            .all(|spacing| spacing.is_finite() && *spacing >= 0.0)
+    );
+}
+
+#[test]
+fn test_dust_regularity() {
+    // the spread shrinks with the regularity, while the average rate is kept
+    let (mean, deviation) = stats(&spacings(2, 0.5, 20000));
+    assert!((mean - 1.0).abs() < 0.03, "mean {mean}");
+    assert!((deviation - 0.5).abs() < 0.03, "deviation {deviation}");
+
+    assert!(spacings(3, 1.0, 100).iter().all(|spacing| *spacing == 1.0));
+    assert!(spacings(3, 2.0, 100).iter().all(|spacing| *spacing == 1.0));
+}
+
+#[test]
+fn test_dust_seed_and_intervals() {
+    assert_eq!(spacings(4, 0.0, 10), spacings(4, 0.0, 10));
+    assert_ne!(spacings(4, 0.0, 10), spacings(5, 0.0, 10));
+
+    let mut dust = Dust::from_seed(6);
+    assert_eq!(dust.next_interval(0.0, 0.0), None);
+    assert_eq!(
+        dust.next_interval(4.0, 1.0),
+        Some(Duration::from_millis(250))
+    );
+}
+
+#[tokio::test]
+async fn test_dust_regular_clock() {
+    let now = Instant::now();
+    let mut out = Vec::new();
+
+    // fully regular dust at 200Hz is a clock with a 5ms period
+    run_for(
+        dg_clock::dust(
+            MockGateOut::new(&mut out),
+            200.0,
+            1.0,
+            Dust::from_seed(7),
+            Duration::from_millis(1),
+            OverlapPolicy::Drop,
+        ),
+        Duration::from_millis(52),
+    )
+    .await;
+
+    let expected: Vec<u64> = (1..=10).map(|i| 5 * i).collect();
+    assert_pulses(now, &gate_pulses(&out), &expected);
+}
+
+#[tokio::test]
+async fn test_dust_zero_rate() {
+    let mut out = Vec::new();
+
+    run_for(
+        dg_clock::dust(
+            MockGateOut::new(&mut out),
+            0.0,
+            0.0,
+            Dust::from_seed(8),
+            Duration::from_millis(1),
+            OverlapPolicy::Drop,
+        ),
+        Duration::from_millis(50),
+    )
+    .await;
+
+    assert!(out.is_empty(), "{out:?}");
+}
+
+#[tokio::test]
+async fn test_dust_poisson_inter_arrivals() {
+    let mut out = Vec::new();
+
+    run_for(
+        dg_clock::dust(
+            MockGateOut::new(&mut out),
+            200.0,
+            0.0,
+            Dust::from_seed(9),
+            Duration::from_micros(100),
+            OverlapPolicy::Queue,
+        ),
+        Duration::from_secs(1),
+    )
+    .await;
+
+    // about 200 pulses, with a standard deviation of about 14
+    let pulses = gate_pulses(&out);
+    assert!(
+        (150..250).contains(&pulses.len()),
+        "{} pulses",
+        pulses.len()
+    );
+
+    // exponential inter-arrival times have a standard deviation close to their mean (5ms)
+    let intervals = pulses
+        .windows(2)
+        .map(|pair| (pair[1].time() - pair[0].time()).as_micros() as f32 / 1000.0)
+        .collect::<Vec<_>>();
+    let (mean, deviation) = stats(&intervals);
+    assert!((4.0..6.5).contains(&mean), "mean {mean}ms");
+    assert!(
+        deviation / mean > 0.7,
+        "deviation {deviation}ms for mean {mean}ms"
+    );
+}
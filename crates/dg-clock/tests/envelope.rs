@@ -0,0 +1,215 @@
+use embassy_time::{Duration, Instant};
+
+use dg_clock::{Adsr, Envelope, EnvelopeCurve, EnvelopeMode};
+
+mod common;
+
+use common::{
+    MockClockIn, MockCvOut, MockGateIn, assert_instant_shortly_after, cv_value_at, millis, run_for,
+};
+
+const SETTINGS: Adsr<f32, f32, f32, f32> = Adsr {
+    attack: 10.0,
+    decay: 20.0,
+    sustain: 0.5,
+    release: 40.0,
+};
+
+/// Advances `envelope` millisecond by millisecond and returns the levels.
+fn run(envelope: &mut Envelope, ms: usize) -> Vec<f32> {
+    (0..ms)
+        .map(|_| envelope.advance(Duration::from_millis(1), &SETTINGS))
+        .collect()
+}
+
+fn assert_close(actual: f32, expected: f32) {
+    assert!((actual - expected).abs() < 1e-3, "{actual} != {expected}");
+}
+
+#[test]
+fn test_curve_shapes() {
+    for curve in [EnvelopeCurve::Linear, EnvelopeCurve::Exponential] {
+        assert_close(curve.shape(0.0), 0.0);
+        assert_close(curve.shape(1.0), 1.0);
+        assert_close(curve.shape(2.0), 1.0);
+        assert!(
+            (1..=10).all(|i| curve.shape(i as f32 / 10.0) > curve.shape((i - 1) as f32 / 10.0))
+        );
+    }
+
+    assert_close(EnvelopeCurve::Linear.shape(0.25), 0.25);
+
+    // exponential segments cover most of their course in the first half
+    assert!(EnvelopeCurve::Exponential.shape(0.5) > 0.85);
+}
+
+#[test]
+fn test_envelope_ad() {
+    let mut envelope = Envelope::new(EnvelopeMode::Ad, EnvelopeCurve::Linear);
+    assert_eq!(run(&mut envelope, 5), [0.0; 5]);
+
+    envelope.gate(true);
+    let levels = run(&mut envelope, 40);
+    assert_close(levels[4], 0.5);
+    assert_close(levels[9], 1.0);
+    assert_close(levels[19], 0.5);
+    assert_close(levels[29], 0.0);
+    assert!(envelope.is_idle());
+
+    // the gate length doesn't matter
+    envelope.gate(true);
+    run(&mut envelope, 5);
+    envelope.gate(false);
+    let levels = run(&mut envelope, 10);
+    assert_close(levels[4], 1.0);
+    assert_close(levels[9], 0.75);
+}
+
+#[test]
+fn test_envelope_adsr() {
+    let mut envelope = Envelope::new(EnvelopeMode::Adsr, EnvelopeCurve::Linear);
+    envelope.gate(true);
+
+    let levels = run(&mut envelope, 100);
+    assert_close(levels[9], 1.0);
+    assert_close(levels[19], 0.75);
+    assert_close(levels[29], 0.5);
+    assert_close(levels[99], 0.5);
+
+    envelope.gate(false);
+    let levels = run(&mut envelope, 40);
+    assert_close(levels[19], 0.25);
+    assert_close(levels[39], 0.0);
+    assert!(envelope.is_idle());
+    assert_eq!(envelope.value(), 0);
+}
+
+#[test]
+fn test_envelope_carries_overshoot() {
+    let mut envelope = Envelope::new(EnvelopeMode::Adsr, EnvelopeCurve::Linear);
+    envelope.gate(true);
+
+    // 3 ms steps don't line up with the end of the 10 ms attack
+    let levels: Vec<f32> = (0..10)
+        .map(|_| envelope.advance(Duration::from_millis(3), &SETTINGS))
+        .collect();
+    assert_close(levels[2], 0.9);
+    assert_close(levels[3], 0.95);
+    assert_close(levels[4], 0.875);
+    assert_close(levels[9], 0.5);
+}
+
+#[test]
+fn test_envelope_ar() {
+    let mut envelope = Envelope::new(EnvelopeMode::Ar, EnvelopeCurve::Linear);
+    envelope.gate(true);
+    let levels = run(&mut envelope, 50);
+    assert_close(levels[49], 1.0);
+    assert_eq!(envelope.value(), u16::MAX);
+
+    // released mid-attack, the release starts from the current level
+    envelope.gate(false);
+    run(&mut envelope, 40);
+    envelope.gate(true);
+    run(&mut envelope, 5);
+    envelope.gate(false);
+    let levels = run(&mut envelope, 20);
+    assert_close(levels[0], 0.5 - 0.5 / 40.0);
+    assert_close(levels[19], 0.25);
+
+    // triggers release right after the attack
+    envelope.trigger();
+    let levels = run(&mut envelope, 60);
+    assert_close(levels[9], 1.0);
+    assert_close(levels[29], 0.5);
+    assert_close(levels[49], 0.0);
+}
+
+#[test]
+fn test_envelope_exponential() {
+    let mut envelope = Envelope::new(EnvelopeMode::Ad, EnvelopeCurve::Exponential);
+    envelope.gate(true);
+
+    let levels = run(&mut envelope, 30);
+    assert!(levels[4] > 0.85, "{levels:?}");
+    assert_close(levels[9], 1.0);
+    assert!(levels[19] < 0.15, "{levels:?}");
+    assert_close(levels[29], 0.0);
+
+    // the attack is concave and the decay convex
+    let attack = &levels[..10];
+    let decay = &levels[10..30];
+    assert!(attack.windows(3).all(|w| w[2] - w[1] < w[1] - w[0]));
+    assert!(decay.windows(3).all(|w| w[1] - w[2] < w[0] - w[1]));
+}
+
+#[test]
+fn test_envelope_retrigger_doesnt_jump() {
+    let mut envelope = Envelope::new(EnvelopeMode::Ad, EnvelopeCurve::Linear);
+    envelope.gate(true);
+    run(&mut envelope, 20);
+
+    // restarting the attack from the current level
+    envelope.gate(true);
+    let levels = run(&mut envelope, 10);
+    assert_close(levels[0], 0.5 + 0.5 / 10.0);
+    assert_close(levels[9], 1.0);
+}
+
+#[tokio::test]
+async fn test_envelope_gate_driven() {
+    let now = Instant::now();
+    let ms = |ms| now + Duration::from_millis(ms);
+    let mut values = Vec::new();
+
+    run_for(
+        dg_clock::envelope(
+            MockGateIn::new([(ms(10), true), (ms(50), false)]),
+            MockCvOut::new(&mut values),
+            Envelope::new(EnvelopeMode::Adsr, EnvelopeCurve::Linear),
+            SETTINGS,
+            Duration::from_millis(1),
+        ),
+        Duration::from_millis(110),
+    )
+    .await;
+
+    let value_at = |at| cv_value_at(&values, at);
+
+    assert_eq!(value_at(ms(9)), 0);
+    // the attack reaches full scale about 10ms after the gate rises
+    let peak = values.iter().find(|(_, value)| *value == u16::MAX).unwrap();
+    assert_instant_shortly_after(peak.0, ms(20));
+    assert_eq!(value_at(ms(48)), u16::MAX / 2 + 1);
+    assert_eq!(value_at(ms(105)), 0);
+
+    // updates stop once the envelope is idle
+    let last = values.last().unwrap().0;
+    assert!(last < ms(100), "{last:?}");
+}
+
+#[tokio::test]
+async fn test_envelope_clock_driven() {
+    let now = Instant::now();
+    let mut values = Vec::new();
+
+    run_for(
+        dg_clock::clock_envelope(
+            MockClockIn::new(millis(now, &[10])),
+            MockCvOut::new(&mut values),
+            Envelope::new(EnvelopeMode::Ar, EnvelopeCurve::Linear),
+            Adsr {
+                release: 10.0,
+                ..SETTINGS
+            },
+            Duration::from_millis(2),
+        ),
+        Duration::from_millis(50),
+    )
+    .await;
+
+    let peak = values.iter().max_by_key(|(_, value)| *value).unwrap();
+    assert_eq!(peak.1, u16::MAX);
+    assert!(peak.0 >= now + Duration::from_millis(20), "{peak:?}");
+    assert_eq!(values.last().unwrap().1, 0);
+}
@@ -0,0 +1,66 @@
+use embassy_time::{Duration, Instant, Timer};
+
+use dg_clock::{PulseGrid, ratchet_offset};
+
+mod common;
+
+use common::simulate;
+
+#[tokio::test]
+async fn test_grid_no_drift() {
+    simulate(async {
+        let origin = Instant::now();
+        let period = Duration::from_millis(1);
+        let mut grid = PulseGrid::new(origin, period);
+
+        for index in 0..1000 {
+            assert_eq!(grid.wait().await, origin + period * index);
+
+            // time spent emitting the pulse
+            Timer::after(Duration::from_micros(200)).await;
+        }
+
+        assert_eq!(grid.index(), 1000);
+        assert_eq!(grid.deadline(), origin + period * 1000);
+
+        let drift = Instant::now() - (origin + period * 999);
+        assert!(drift < Duration::from_millis(3), "{drift:?}");
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_grid_late_caller_catches_up() {
+    simulate(async {
+        let origin = Instant::now();
+        let mut grid = PulseGrid::new(origin, Duration::from_millis(5));
+
+        Timer::after(Duration::from_millis(12)).await;
+
+        // the missed deadlines are returned right away, the grid isn't shifted
+        let late = Instant::now();
+        assert_eq!(grid.wait().await, origin);
+        assert_eq!(grid.wait().await, origin + Duration::from_millis(5));
+        assert_eq!(grid.wait().await, origin + Duration::from_millis(10));
+        assert!(Instant::now() - late < Duration::from_millis(1));
+
+        assert_eq!(grid.wait().await, origin + Duration::from_millis(15));
+        assert!(Instant::now() >= origin + Duration::from_millis(15));
+    })
+    .await;
+}
+
+#[test]
+fn test_grid_curved() {
+    let origin = Instant::from_millis(100);
+    let span = Duration::from_millis(40);
+    let grid = PulseGrid::curved(origin, span, 4, 0.5);
+
+    for index in 0..4 {
+        assert_eq!(grid.at(index), origin + ratchet_offset(span, index, 4, 0.5));
+    }
+
+    // past the curve, the grid carries on at the mean spacing
+    assert_eq!(grid.at(4), origin + span);
+    assert_eq!(grid.at(6), origin + span + Duration::from_millis(20));
+}
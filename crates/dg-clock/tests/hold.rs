@@ -0,0 +1,130 @@
+use embassy_time::{Duration, Instant};
+
+use dg_clock::{NoiseSource, ParameterSource, SampleSource, SlewLimiter};
+use dg_noise::NoiseGenerator;
+
+mod common;
+
+use common::{MockClockIn, MockCvIn, MockCvOut, MockGateIn, cv_value_at, millis, run_for};
+
+/// Noise source counting up by a quarter of the range.
+struct Steps(u16);
+
+impl NoiseGenerator for Steps {
+    fn sample(&mut self) -> u16 {
+        self.0 = self.0.wrapping_add(0x4000);
+        self.0
+    }
+}
+
+/// CV input rising by 1000 every millisecond from `start`.
+fn ramp(start: Instant) -> MockCvIn<impl FnMut(Instant) -> u16> {
+    MockCvIn::new(move |now: Instant| {
+        now.saturating_duration_since(start).as_micros().min(65_000) as u16
+    })
+}
+
+#[test]
+fn test_slew_limiter() {
+    let mut slew = SlewLimiter::new();
+    slew.set_target(u16::MAX);
+    assert!(!slew.is_settled());
+
+    // half the range in 5ms
+    assert_eq!(slew.advance(Duration::from_millis(5), 10.0), 0x8000);
+    assert_eq!(slew.advance(Duration::from_millis(10), 10.0), u16::MAX);
+    assert!(slew.is_settled());
+
+    slew.set_target(0x1000);
+    assert_eq!(slew.advance(Duration::from_ticks(0), 0.0), 0x1000);
+}
+
+#[tokio::test]
+async fn test_sources() {
+    assert_eq!(NoiseSource(Steps(0)).sample().await, 0x4000);
+    assert_eq!(ParameterSource(0.5).sample().await, 0x8000);
+    assert_eq!(ParameterSource(2.0).sample().await, u16::MAX);
+    assert_eq!(ParameterSource(-1.0).sample().await, 0);
+}
+
+#[tokio::test]
+async fn test_sample_and_hold() {
+    let now = Instant::now();
+    let ms = |ms| now + Duration::from_millis(ms);
+    let mut values = Vec::new();
+
+    run_for(
+        dg_clock::sample_and_hold(
+            MockClockIn::new(millis(now, &[10, 20, 30])),
+            NoiseSource(Steps(0)),
+            MockCvOut::new(&mut values),
+            0.0,
+            Duration::from_millis(1),
+        ),
+        Duration::from_millis(40),
+    )
+    .await;
+
+    assert_eq!(cv_value_at(&values, ms(5)), 0);
+    assert_eq!(cv_value_at(&values, ms(15)), 0x4000);
+    assert_eq!(cv_value_at(&values, ms(25)), 0x8000);
+    assert_eq!(cv_value_at(&values, ms(35)), 0xc000);
+
+    // nothing is written between clocks without slew
+    assert_eq!(values.len(), 3, "{values:?}");
+}
+
+#[tokio::test]
+async fn test_sample_and_hold_slew() {
+    let now = Instant::now();
+    let ms = |ms| now + Duration::from_millis(ms);
+    let mut values = Vec::new();
+
+    // the whole range in 40ms, so a quarter of it in 10ms
+    run_for(
+        dg_clock::sample_and_hold(
+            MockClockIn::new(millis(now, &[10])),
+            NoiseSource(Steps(0)),
+            MockCvOut::new(&mut values),
+            40.0,
+            Duration::from_millis(1),
+        ),
+        Duration::from_millis(30),
+    )
+    .await;
+
+    let halfway = cv_value_at(&values, ms(15)) as i32;
+    assert!((halfway - 0x2000).abs() < 0x800, "{halfway:#x}");
+    assert_eq!(cv_value_at(&values, ms(25)), 0x4000);
+}
+
+#[tokio::test]
+async fn test_track_and_hold() {
+    let now = Instant::now();
+    let ms = |ms| now + Duration::from_millis(ms);
+    let mut values = Vec::new();
+
+    run_for(
+        dg_clock::track_and_hold(
+            MockGateIn::new([(ms(10), true), (ms(20), false)]),
+            ramp(now),
+            MockCvOut::new(&mut values),
+            0.0,
+            Duration::from_millis(1),
+        ),
+        Duration::from_millis(40),
+    )
+    .await;
+
+    let close_to = |at: u64, expected: i32| {
+        let value = cv_value_at(&values, ms(at)) as i32;
+        assert!((value - expected).abs() < 2000, "at {at}ms: {value}");
+    };
+
+    // held low until the gate goes high, tracks, then holds the value at the falling edge
+    assert_eq!(cv_value_at(&values, ms(9)), 0);
+    close_to(12, 12_000);
+    close_to(18, 18_000);
+    close_to(30, 20_000);
+    close_to(39, 20_000);
+}
@@ -0,0 +1,117 @@
+use embassy_time::{Duration, Instant};
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+use dg_clock::JitterDistribution;
+
+mod common;
+
+use common::{MockClockIn, MockClockOut, Pulse, millis, run_for};
+
+async fn run_humanize(
+    events: &[u64],
+    max_deviation: f32,
+    distribution: JitterDistribution,
+) -> (Instant, Vec<Pulse>) {
+    let now = Instant::now();
+    let mut pulses = Vec::new();
+
+    run_for(
+        dg_clock::humanize(
+            MockClockIn::new(millis(now, events)),
+            MockClockOut::new(&mut pulses),
+            max_deviation,
+            distribution,
+            SmallRng::seed_from_u64(0),
+            Duration::from_millis(1),
+        ),
+        Duration::from_millis(events.last().unwrap() + 2 * max_deviation as u64 + 10),
+    )
+    .await;
+
+    (now, pulses)
+}
+
+fn samples(distribution: JitterDistribution, seed: u64, max_deviation: f32) -> Vec<f32> {
+    let mut rng = SmallRng::seed_from_u64(seed);
+    (0..10_000)
+        .map(|_| distribution.sample(&mut rng, max_deviation))
+        .collect()
+}
+
+#[test]
+fn test_jitter_bounds_and_seed() {
+    for distribution in [JitterDistribution::Uniform, JitterDistribution::Gaussian] {
+        let values = samples(distribution, 1, 5.0);
+        assert!(values.iter().all(|v| (-5.0..=5.0).contains(v)));
+
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        assert!(mean.abs() < 0.2, "{distribution:?} mean {mean}");
+
+        // the same seed produces the same offsets
+        assert_eq!(values, samples(distribution, 1, 5.0));
+        assert_ne!(values, samples(distribution, 2, 5.0));
+
+        assert!(samples(distribution, 1, 0.0).iter().all(|v| *v == 0.0));
+        assert!(samples(distribution, 1, -3.0).iter().all(|v| *v == 0.0));
+    }
+}
+
+#[test]
+fn test_jitter_distribution_shape() {
+    let within_third = |distribution| {
+        let values = samples(distribution, 3, 3.0);
+        values.iter().filter(|v| v.abs() <= 1.0).count() as f32 / values.len() as f32
+    };
+
+    // a third of uniform offsets, and about 68% (one standard deviation) of gaussian ones
+    let uniform = within_third(JitterDistribution::Uniform);
+    let gaussian = within_third(JitterDistribution::Gaussian);
+    assert!((uniform - 1.0 / 3.0).abs() < 0.03, "{uniform}");
+    assert!((gaussian - 0.68).abs() < 0.03, "{gaussian}");
+}
+
+#[tokio::test]
+async fn test_humanize_no_deviation() {
+    let (now, pulses) = run_humanize(&[10, 20, 30], 0.0, JitterDistribution::Uniform).await;
+
+    assert_eq!(pulses.len(), 3, "{pulses:?}");
+    for (pulse, ms) in pulses.iter().zip([10, 20, 30]) {
+        pulse.assert_shortly_after(now + Duration::from_millis(ms));
+    }
+}
+
+#[tokio::test]
+async fn test_humanize_within_bounds() {
+    let events = [10, 30, 50, 70];
+    let (now, pulses) = run_humanize(&events, 5.0, JitterDistribution::Gaussian).await;
+
+    // offsets are centered around a latency of the maximum deviation
+    assert_eq!(pulses.len(), events.len(), "{pulses:?}");
+    for (pulse, ms) in pulses.iter().zip(events) {
+        let earliest = now + Duration::from_millis(ms);
+        let latest = earliest + Duration::from_millis(10 + 3);
+        assert!(
+            pulse.time() >= earliest && pulse.time() <= latest,
+            "{pulse:?} not within {ms} ms + 10 ms"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_humanize_never_reorders() {
+    // the deviation is larger than the input period
+    let events = [10, 12, 14, 16, 18, 20];
+    let (now, pulses) = run_humanize(&events, 8.0, JitterDistribution::Uniform).await;
+
+    assert_eq!(pulses.len(), events.len(), "{pulses:?}");
+    for (pulse, ms) in pulses.iter().zip(events) {
+        assert!(
+            pulse.time() >= now + Duration::from_millis(ms),
+            "{pulses:?}"
+        );
+    }
+    for pair in pulses.windows(2) {
+        assert!(pair[1].time() >= pair[0].end(), "{pulses:?}");
+    }
+}
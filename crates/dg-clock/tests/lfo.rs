@@ -0,0 +1,217 @@
+use embassy_time::{Duration, Instant};
+
+use dg_clock::{Lfo, LfoShape};
+use dg_noise::NoiseGenerator;
+
+mod common;
+
+use common::{MockClockIn, MockCvOut, cv_value_at, millis, run_for};
+
+/// Noise source counting up by a quarter of the range.
+struct Steps(u16);
+
+impl NoiseGenerator for Steps {
+    fn sample(&mut self) -> u16 {
+        self.0 = self.0.wrapping_add(0x4000);
+        self.0
+    }
+}
+
+fn levels(shape: LfoShape, phases: &[f32]) -> Vec<f32> {
+    let mut lfo = Lfo::new(shape);
+    phases
+        .iter()
+        .map(|phase| {
+            lfo.sync(*phase);
+            lfo.level()
+        })
+        .collect()
+}
+
+fn assert_levels(actual: &[f32], expected: &[f32]) {
+    assert_eq!(actual.len(), expected.len());
+    for (actual, expected) in actual.iter().zip(expected) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "{actual:?} != {expected:?}"
+        );
+    }
+}
+
+const PHASES: [f32; 5] = [0.0, 0.25, 0.5, 0.75, 0.999];
+
+#[test]
+fn test_lfo_shapes() {
+    assert_levels(
+        &levels(LfoShape::Sine, &PHASES),
+        &[0.5, 1.0, 0.5, 0.0, 0.497],
+    );
+    assert_levels(
+        &levels(LfoShape::Triangle, &PHASES),
+        &[0.0, 0.5, 1.0, 0.5, 0.002],
+    );
+    assert_levels(
+        &levels(LfoShape::Saw, &PHASES),
+        &[1.0, 0.75, 0.5, 0.25, 0.001],
+    );
+    assert_levels(
+        &levels(LfoShape::Ramp, &PHASES),
+        &[0.0, 0.25, 0.5, 0.75, 0.999],
+    );
+    assert_levels(
+        &levels(LfoShape::Square, &PHASES),
+        &[1.0, 1.0, 0.0, 0.0, 0.0],
+    );
+}
+
+#[test]
+fn test_lfo_sample_and_hold() {
+    let mut lfo = Lfo::with_noise(LfoShape::SampleAndHold, Steps(0));
+    assert_eq!(lfo.value(), 0x4000);
+
+    // held for the whole cycle
+    lfo.advance(0.6);
+    assert_eq!(lfo.value(), 0x4000);
+
+    // new level when wrapping around, resetting or syncing past the end of the cycle
+    lfo.advance(0.6);
+    assert_eq!(lfo.value(), 0x8000);
+    assert!((lfo.phase() - 0.2).abs() < 1e-6);
+    lfo.reset();
+    assert_eq!(lfo.value(), 0xc000);
+    lfo.advance(0.9);
+    lfo.sync(0.1);
+    assert_eq!(lfo.value(), 0);
+
+    // syncing slightly back is not a new cycle
+    lfo.sync(0.05);
+    assert_eq!(lfo.value(), 0);
+}
+
+#[test]
+fn test_lfo_draws_only_for_sample_and_hold() {
+    let mut lfo = Lfo::with_noise(LfoShape::Ramp, Steps(0));
+    lfo.advance(2.5);
+    lfo.reset();
+
+    // the first level is drawn when switching to sample and hold
+    lfo.set_shape(LfoShape::SampleAndHold);
+    assert_eq!(lfo.value(), 0x4000);
+    lfo.set_shape(LfoShape::SampleAndHold);
+    assert_eq!(lfo.value(), 0x4000);
+
+    let mut lfo = Lfo::new(LfoShape::SampleAndHold);
+    assert_eq!(lfo.value(), 0x8000);
+    lfo.advance(1.5);
+    assert_eq!(lfo.value(), 0x8000);
+}
+
+#[test]
+fn test_lfo_advance() {
+    let mut lfo = Lfo::new(LfoShape::Ramp);
+    lfo.advance(0.25);
+    assert_eq!(lfo.value(), 0x4000);
+    lfo.advance(-1.0);
+    assert_eq!(lfo.value(), 0x4000);
+    lfo.advance(2.5);
+    assert_eq!(lfo.value(), 0xbfff);
+}
+
+#[tokio::test]
+async fn test_lfo_free_running() {
+    let now = Instant::now();
+    let ms = |ms| now + Duration::from_millis(ms);
+    let mut values = Vec::new();
+
+    // 25 Hz ramp, reset at 50ms
+    run_for(
+        dg_clock::lfo(
+            MockClockIn::new(millis(now, &[50])),
+            MockCvOut::new(&mut values),
+            Lfo::new(LfoShape::Ramp),
+            25.0,
+            Duration::from_millis(1),
+        ),
+        Duration::from_millis(70),
+    )
+    .await;
+
+    let tolerance = 0x1800;
+    for (at, expected) in [(10, 0x4000), (30, 0xc000), (48, 0x3333), (60, 0x4000)] {
+        let value = cv_value_at(&values, ms(at)) as i32;
+        assert!(
+            (value - expected).abs() < tolerance,
+            "at {at}ms: {value:#x} != {expected:#x}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_lfo_synced_multiply() {
+    let now = Instant::now();
+    let ms = |ms| now + Duration::from_millis(ms);
+    let mut values = Vec::new();
+
+    // two ramp cycles per 20ms clock
+    run_for(
+        dg_clock::lfo_synced(
+            MockClockIn::new(millis(now, &[10, 30, 50])),
+            MockClockIn::new([]),
+            MockCvOut::new(&mut values),
+            Lfo::new(LfoShape::Ramp),
+            2,
+            1,
+            Duration::from_millis(1),
+        ),
+        Duration::from_millis(60),
+    )
+    .await;
+
+    // the LFO doesn't move until the period is known
+    assert_eq!(cv_value_at(&values, ms(25)), 0);
+
+    let tolerance = 0x2000;
+    for (at, expected) in [(35, 0x8000), (43, 0x4ccc), (48, 0xcccc), (55, 0x8000)] {
+        let value = cv_value_at(&values, ms(at)) as i32;
+        assert!(
+            (value - expected).abs() < tolerance,
+            "at {at}ms: {value:#x} != {expected:#x}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_lfo_synced_divide() {
+    let now = Instant::now();
+    let ms = |ms| now + Duration::from_millis(ms);
+    let mut values = Vec::new();
+
+    // one square cycle every 4 clocks
+    run_for(
+        dg_clock::lfo_synced(
+            MockClockIn::new(millis(now, &[10, 20, 30, 40, 50, 60, 70, 80])),
+            MockClockIn::new(millis(now, &[65])),
+            MockCvOut::new(&mut values),
+            Lfo::new(LfoShape::Square),
+            1,
+            4,
+            Duration::from_millis(1),
+        ),
+        Duration::from_millis(100),
+    )
+    .await;
+
+    // high for two clocks, low for two clocks, then restarted by the reset
+    for (at, high) in [
+        (15, true),
+        (35, false),
+        (45, false),
+        (55, true),
+        (67, true),
+        (75, true),
+        (93, false),
+    ] {
+        let value = cv_value_at(&values, ms(at));
+        assert_eq!(value == u16::MAX, high, "at {at}ms: {value:#x}");
+    }
+}
@@ -0,0 +1,120 @@
+use embassy_time::{Duration, Instant};
+
+use dg_clock::{LogicOp, LogicOutput};
+
+mod common;
+
+use common::{
+    MockClockIn, MockGateOut, Pulse, assert_gates, assert_pulses, gate_pulses, millis, run_for,
+};
+
+/// Windows: A+B at 10/11, A alone at 30, B alone at 40, A+B at 50/53.
+async fn run_logic(op: LogicOp, output: LogicOutput) -> (Instant, Vec<Pulse>) {
+    let now = Instant::now();
+    let mut edges = Vec::new();
+
+    run_for(
+        dg_clock::clock_logic(
+            [
+                MockClockIn::new(millis(now, &[10, 30, 50])),
+                MockClockIn::new(millis(now, &[11, 40, 53])),
+            ],
+            MockGateOut::new(&mut edges),
+            op,
+            Duration::from_millis(4),
+            output,
+        ),
+        Duration::from_millis(70),
+    )
+    .await;
+
+    (now, gate_pulses(&edges))
+}
+
+async fn run_triggers(op: LogicOp) -> (Instant, Vec<Pulse>) {
+    run_logic(op, LogicOutput::Trigger(Duration::from_millis(2))).await
+}
+
+#[tokio::test]
+async fn test_logic_and() {
+    // the window closes as soon as both inputs fired
+    let (now, pulses) = run_triggers(LogicOp::And).await;
+    assert_pulses(now, &pulses, &[11, 53]);
+
+    for pulse in &pulses {
+        pulse.assert_lasted_about(Duration::from_millis(2));
+    }
+}
+
+#[tokio::test]
+async fn test_logic_or() {
+    // a lone edge is only evaluated once its window is over
+    let (now, pulses) = run_triggers(LogicOp::Or).await;
+    assert_pulses(now, &pulses, &[11, 34, 44, 53]);
+}
+
+#[tokio::test]
+async fn test_logic_xor_nand() {
+    let (now, pulses) = run_triggers(LogicOp::Xor).await;
+    assert_pulses(now, &pulses, &[34, 44]);
+
+    let (now, pulses) = run_triggers(LogicOp::Nand).await;
+    assert_pulses(now, &pulses, &[34, 44]);
+}
+
+#[tokio::test]
+async fn test_logic_and_gate() {
+    // high from the first coincidence until the next window evaluating false, the last gate
+    // is still open at the end of the run
+    let (now, pulses) = run_logic(LogicOp::And, LogicOutput::Gate).await;
+    assert_gates(now, &pulses, &[(11, 34)]);
+}
+
+#[tokio::test]
+async fn test_logic_toggle() {
+    let (now, pulses) = run_logic(LogicOp::Toggle, LogicOutput::Gate).await;
+    assert_gates(now, &pulses, &[(11, 34), (44, 53)]);
+
+    // triggers on rising edges only
+    let (now, pulses) = run_triggers(LogicOp::Toggle).await;
+    assert_pulses(now, &pulses, &[11, 44]);
+}
+
+#[tokio::test]
+async fn test_logic_latch() {
+    // reset wins when set and reset coincide
+    let (now, pulses) = run_logic(LogicOp::Latch, LogicOutput::Gate).await;
+    assert_gates(now, &pulses, &[(34, 44)]);
+}
+
+async fn run_pair(window: u64) -> (Instant, Vec<Pulse>) {
+    let now = Instant::now();
+    let mut edges = Vec::new();
+
+    run_for(
+        dg_clock::clock_logic(
+            [
+                MockClockIn::new(millis(now, &[10])),
+                MockClockIn::new(millis(now, &[13])),
+            ],
+            MockGateOut::new(&mut edges),
+            LogicOp::And,
+            Duration::from_millis(window),
+            LogicOutput::Trigger(Duration::from_millis(2)),
+        ),
+        Duration::from_millis(25),
+    )
+    .await;
+
+    (now, gate_pulses(&edges))
+}
+
+#[tokio::test]
+async fn test_logic_window() {
+    // 3ms apart: simultaneous with a 4ms window, distinct with a 2ms one
+    let (now, pulses) = run_pair(4).await;
+    assert_pulses(now, &pulses, &[13]);
+
+    let (_, pulses) = run_pair(2).await;
+    assert!(pulses.is_empty(), "{pulses:?}");
+}
@@ -0,0 +1,228 @@
+use std::collections::VecDeque;
+
+use embassy_time::{Duration, Instant, Timer};
+
+use dg_clock::{MidiClockEvent, MidiClockIn, MidiClockOut, MidiClockParser, clock_to_midi};
+use dg_types::{ByteIn, ByteOut, ClockIn, ClockOut};
+
+mod common;
+
+use common::{MockClockIn, Pulse, assert_pulses, millis, run_for};
+
+/// Byte stream replaying a recording, each byte being received at its instant.
+struct MockByteIn {
+    bytes: VecDeque<(Instant, u8)>,
+}
+
+impl MockByteIn {
+    fn new(now: Instant, recording: &[(u64, &[u8])]) -> Self {
+        let bytes = recording
+            .iter()
+            .flat_map(|(ms, bytes)| {
+                bytes
+                    .iter()
+                    .map(move |byte| (now + Duration::from_millis(*ms), *byte))
+            })
+            .collect();
+        Self { bytes }
+    }
+}
+
+impl ByteIn for MockByteIn {
+    async fn read_byte(&mut self) -> u8 {
+        match self.bytes.front().copied() {
+            Some((at, byte)) => {
+                Timer::at(at).await;
+                self.bytes.pop_front();
+                byte
+            }
+            None => std::future::pending().await,
+        }
+    }
+}
+
+/// Byte stream recording everything written to it.
+struct MockByteOut<'a> {
+    bytes: &'a mut Vec<u8>,
+}
+
+impl ByteOut for MockByteOut<'_> {
+    async fn write_byte(&mut self, byte: u8) {
+        self.bytes.push(byte);
+    }
+}
+
+/// Byte stream recording everything written to it, with the instant it was written at.
+struct TimedByteOut<'a> {
+    bytes: &'a mut Vec<(Instant, u8)>,
+}
+
+impl ByteOut for TimedByteOut<'_> {
+    async fn write_byte(&mut self, byte: u8) {
+        self.bytes.push((Instant::now(), byte));
+    }
+}
+
+fn parse(bytes: &[u8]) -> Vec<MidiClockEvent> {
+    let mut parser = MidiClockParser::new();
+    bytes.iter().filter_map(|byte| parser.push(*byte)).collect()
+}
+
+#[test]
+fn test_midi_parser() {
+    use MidiClockEvent::*;
+
+    // clocks interleaved within a note on and a song position, running status data bytes, and
+    // active sensing
+    let recording = [
+        0xFA, 0xF8, 0x90, 0x3C, 0xF8, 0x64, 0x3E, 0x64, 0xF8, 0xFE, 0xF2, 0x10, 0xF8, 0x02, 0x40,
+        0xFC, 0xFB,
+    ];
+
+    assert_eq!(
+        parse(&recording),
+        [
+            Start,
+            Clock,
+            Clock,
+            Clock,
+            Clock,
+            SongPosition(0x02 << 7 | 0x10),
+            Stop,
+            Continue
+        ]
+    );
+
+    // a song position interrupted by another status byte is dropped
+    assert_eq!(parse(&[0xF2, 0x10, 0x80, 0x02, 0x7F]), []);
+}
+
+#[test]
+fn test_midi_encode_round_trip() {
+    use MidiClockEvent::*;
+
+    let events = [
+        Start,
+        Clock,
+        Stop,
+        Continue,
+        SongPosition(0),
+        SongPosition(0x3FFF),
+    ];
+    for event in events {
+        let (bytes, len) = event.encode();
+        assert_eq!(parse(&bytes[..len]), [event]);
+    }
+}
+
+#[tokio::test]
+async fn test_midi_clock_in() {
+    let now = Instant::now();
+
+    let clocks = (0..12).map(|i| (10 + 2 * i, [0xF8].as_slice()));
+    let recording = [(5, [0xFA].as_slice())]
+        .into_iter()
+        .chain(clocks)
+        .chain([
+            // clocks are ignored while stopped
+            (35, [0xFC].as_slice()),
+            (36, &[0xF8]),
+            // resume from the second sixteenth
+            (38, &[0xF2, 0x01, 0x00]),
+            (40, &[0xFB]),
+            (42, &[0xF8]),
+            (44, &[0xF8]),
+        ])
+        .collect::<Vec<_>>();
+
+    let mut clock_in = MidiClockIn::new(MockByteIn::new(now, &recording), 6);
+    let mut pulses = Vec::new();
+    run_for(
+        async {
+            loop {
+                let at = clock_in.wait().await;
+                pulses.push(Pulse::new(at, Duration::from_ticks(0)));
+            }
+        },
+        Duration::from_millis(60),
+    )
+    .await;
+
+    assert_pulses(now, &pulses, &[10, 22, 42]);
+
+    assert!(clock_in.is_running());
+    assert_eq!(clock_in.position(), 8);
+}
+
+#[tokio::test]
+async fn test_midi_clock_out() {
+    let mut bytes = Vec::new();
+    let mut clock_out = MidiClockOut::new(MockByteOut { bytes: &mut bytes });
+
+    clock_out.start().await;
+    clock_out.emit_pulse(Duration::from_millis(5)).await;
+    clock_out.emit_pulse(Duration::from_millis(5)).await;
+    clock_out.stop().await;
+    clock_out.song_position(300).await;
+    clock_out.resume().await;
+
+    assert_eq!(bytes, [0xFA, 0xF8, 0xF8, 0xFC, 0xF2, 0x2C, 0x02, 0xFB]);
+}
+
+/// Runs [`clock_to_midi`] until `until` and returns the instants of the timing clocks sent.
+async fn run_clock_to_midi(clock_in: MockClockIn, ppqn: u32, until: Instant) -> Vec<Instant> {
+    let mut bytes = Vec::new();
+    let mut midi_out = MidiClockOut::new(TimedByteOut { bytes: &mut bytes });
+
+    run_for(
+        clock_to_midi(clock_in, &mut midi_out, ppqn),
+        until - Instant::now(),
+    )
+    .await;
+
+    assert!(bytes.iter().all(|(_, byte)| *byte == 0xF8), "{bytes:?}");
+    bytes.iter().map(|(at, _)| *at).collect()
+}
+
+#[tokio::test]
+async fn test_clock_to_midi_beat_clock() {
+    let now = Instant::now();
+    let ms = |ms| now + Duration::from_millis(ms);
+
+    // one pulse per quarter note, every 24ms
+    let clocks = run_clock_to_midi(
+        MockClockIn::new(millis(now, &[10, 34, 58])),
+        1,
+        now + Duration::from_micros(69_500),
+    )
+    .await;
+
+    // the period of the first beat is unknown, its clocks are caught up on the second beat
+    let mut expected = vec![ms(10)];
+    expected.extend([ms(34); 24]);
+    expected.extend((35..=57).map(ms));
+
+    // the following beats are interpolated, a clock every 1ms
+    expected.extend((58..=69).map(ms));
+    assert_eq!(clocks, expected);
+}
+
+#[tokio::test]
+async fn test_clock_to_midi_resolutions() {
+    let now = Instant::now();
+    let ms = |ms| now + Duration::from_millis(ms);
+
+    // at 24 PPQN, clocks are passed through
+    let clocks = run_clock_to_midi(MockClockIn::new(millis(now, &[10, 12, 14])), 24, ms(20)).await;
+    assert_eq!(clocks, [ms(10), ms(12), ms(14)]);
+
+    // at 48 PPQN, every other pulse sends a clock
+    let clocks =
+        run_clock_to_midi(MockClockIn::new(millis(now, &[30, 31, 32, 33])), 48, ms(40)).await;
+    assert_eq!(clocks, [ms(30), ms(32)]);
+
+    // at 8 PPQN, three clocks per pulse
+    let clocks = run_clock_to_midi(MockClockIn::new(millis(now, &[70, 76, 82])), 8, ms(85)).await;
+    let expected = [70, 76, 76, 76, 78, 80, 82, 84].map(ms);
+    assert_eq!(clocks, expected);
+}
@@ -0,0 +1,103 @@
+use embassy_time::{Duration, Instant};
+
+use dg_clock::{ClockEvent, ClockMonitor, FallbackClock, InternalClock};
+use dg_types::ClockIn;
+
+mod common;
+
+use common::{MockClockIn, assert_instant_shortly_after, millis, run_for, simulate};
+
+#[tokio::test]
+async fn test_wait_timeout() {
+    simulate(async {
+        let now = Instant::now();
+        let mut clock_in = MockClockIn::new(millis(now, &[10]));
+
+        let pulse = clock_in.wait_timeout(Duration::from_millis(20)).await;
+        assert_eq!(pulse, Some(now + Duration::from_millis(10)));
+
+        assert_eq!(clock_in.wait_timeout(Duration::from_millis(10)).await, None);
+        assert_instant_shortly_after(Instant::now(), now + Duration::from_millis(20));
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_clock_monitor_events() {
+    let now = Instant::now();
+    let ms = |ms| now + Duration::from_millis(ms);
+    let mut monitor = ClockMonitor::new(
+        MockClockIn::new(millis(now, &[10, 20, 30, 80, 90])),
+        Duration::from_millis(25),
+    );
+    assert!(!monitor.is_present());
+
+    let mut events = Vec::new();
+    run_for(
+        async {
+            loop {
+                events.push(monitor.next_event().await);
+            }
+        },
+        Duration::from_millis(100),
+    )
+    .await;
+
+    assert_eq!(
+        events,
+        [
+            ClockEvent::ClockPresent(ms(10)),
+            ClockEvent::Pulse(ms(20)),
+            ClockEvent::Pulse(ms(30)),
+            ClockEvent::ClockLost(ms(55)),
+            ClockEvent::ClockPresent(ms(80)),
+            ClockEvent::Pulse(ms(90)),
+        ]
+    );
+    assert!(monitor.is_present());
+}
+
+#[tokio::test]
+async fn test_fallback_clock() {
+    let now = Instant::now();
+    let ms = |ms| now + Duration::from_millis(ms);
+
+    // the internal clock runs at 6000 BPM -> 10ms period
+    let mut clock = FallbackClock::new(
+        ClockMonitor::new(
+            MockClockIn::new(millis(now, &[10, 20, 30, 83, 93])),
+            Duration::from_millis(15),
+        ),
+        InternalClock::new(6000.0),
+    );
+
+    let mut pulses = Vec::new();
+    run_for(
+        async {
+            loop {
+                let instant = clock.wait().await;
+                pulses.push((instant, clock.is_external()));
+            }
+        },
+        Duration::from_millis(100),
+    )
+    .await;
+
+    // the clock is lost at 45ms, the internal clock takes over on the external grid
+    let expected = [
+        (10, true),
+        (20, true),
+        (30, true),
+        (50, false),
+        (60, false),
+        (70, false),
+        (80, false),
+        (83, true),
+        (93, true),
+    ];
+    assert_eq!(pulses.len(), expected.len(), "{pulses:?}");
+    for ((instant, external), (at, expected_external)) in pulses.iter().zip(expected) {
+        assert_instant_shortly_after(*instant, ms(at));
+        assert_eq!(*external, expected_external, "at {at}ms");
+    }
+}
@@ -0,0 +1,81 @@
+use embassy_time::{Duration, Instant};
+
+mod common;
+
+use common::{MockClockIn, MockGateOut, assert_pulses, gate_pulses, millis, run_for};
+
+#[tokio::test]
+async fn test_polyrhythm_phase_aligned() {
+    let now = Instant::now();
+    let mut edges_2 = Vec::new();
+    let mut edges_3 = Vec::new();
+
+    run_for(
+        dg_clock::polyrhythm(
+            MockClockIn::new(millis(now, &[10, 40, 70])),
+            MockClockIn::new([]),
+            [
+                MockGateOut::new(&mut edges_2),
+                MockGateOut::new(&mut edges_3),
+            ],
+            [2, 3],
+            1,
+            Duration::from_millis(2),
+        ),
+        Duration::from_millis(95),
+    )
+    .await;
+
+    // the first cycle only has its downbeat, since the period isn't known yet
+    assert_pulses(now, &gate_pulses(&edges_2), &[10, 40, 55, 70, 85]);
+    assert_pulses(now, &gate_pulses(&edges_3), &[10, 40, 50, 60, 70, 80, 90]);
+
+    for pulse in gate_pulses(&edges_3) {
+        assert!(pulse.duration() >= Duration::from_millis(2));
+        assert!(pulse.duration() < Duration::from_millis(5));
+    }
+}
+
+#[tokio::test]
+async fn test_polyrhythm_multi_beat_cycle() {
+    let now = Instant::now();
+    let mut edges = Vec::new();
+
+    run_for(
+        dg_clock::polyrhythm(
+            MockClockIn::new(millis(now, &[10, 40, 70, 100, 130])),
+            MockClockIn::new([]),
+            [MockGateOut::new(&mut edges)],
+            [3],
+            2,
+            Duration::from_millis(2),
+        ),
+        Duration::from_millis(135),
+    )
+    .await;
+
+    // the pulse at 30ms is skipped, since the period was only known at 40ms
+    assert_pulses(now, &gate_pulses(&edges), &[10, 50, 70, 90, 110, 130]);
+}
+
+#[tokio::test]
+async fn test_polyrhythm_reset() {
+    let now = Instant::now();
+    let mut edges = Vec::new();
+
+    run_for(
+        dg_clock::polyrhythm(
+            MockClockIn::new(millis(now, &[10, 40, 70, 100, 130])),
+            MockClockIn::new(millis(now, &[80])),
+            [MockGateOut::new(&mut edges)],
+            [3],
+            2,
+            Duration::from_millis(2),
+        ),
+        Duration::from_millis(135),
+    )
+    .await;
+
+    // the cycle started at 70ms is interrupted and a new one starts at 100ms
+    assert_pulses(now, &gate_pulses(&edges), &[10, 50, 70, 100, 120]);
+}
@@ -0,0 +1,116 @@
+use embassy_time::{Duration, Instant};
+
+use dg_clock::{BarPosition, OverlapPolicy, PositionTracker, TimeSignature};
+
+mod common;
+
+use common::{MockClockIn, MockGateOut, assert_pulses, gate_pulses, millis, run_for};
+
+fn positions(tracker: &mut PositionTracker, count: usize) -> Vec<String> {
+    (0..count)
+        .map(|_| tracker.tick().to_string())
+        .collect::<Vec<_>>()
+}
+
+#[test]
+fn test_position_common_time() {
+    let mut tracker = PositionTracker::new(2, TimeSignature::COMMON);
+    assert_eq!(tracker.position(), None);
+
+    assert_eq!(
+        positions(&mut tracker, 10),
+        [
+            "1:1:0", "1:1:1", "1:2:0", "1:2:1", "1:3:0", "1:3:1", "1:4:0", "1:4:1", "2:1:0",
+            "2:1:1"
+        ]
+    );
+    assert_eq!(
+        tracker.position(),
+        Some(BarPosition {
+            bar: 1,
+            beat: 0,
+            tick: 1
+        })
+    );
+
+    tracker.reset();
+    assert_eq!(tracker.position(), None);
+    assert_eq!(tracker.tick().to_string(), "1:1:0");
+}
+
+#[test]
+fn test_position_time_signatures() {
+    // eighth note beats last half a quarter note
+    let mut tracker = PositionTracker::new(4, TimeSignature::new(7, 8));
+    let downbeats = (0..30)
+        .map(|_| tracker.tick())
+        .enumerate()
+        .filter(|(_, position)| position.is_downbeat())
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    assert_eq!(downbeats, [0, 14, 28]);
+
+    // beats shorter than a pulse last a single pulse
+    assert_eq!(TimeSignature::new(4, 16).pulses_per_beat(2), 1);
+    assert_eq!(TimeSignature::new(6, 8).pulses_per_beat(24), 12);
+
+    // uneven divisions are truncated
+    assert_eq!(TimeSignature::new(7, 7).pulses_per_beat(24), 13);
+}
+
+#[test]
+fn test_position_signature_change() {
+    let mut tracker = PositionTracker::new(1, TimeSignature::COMMON);
+    positions(&mut tracker, 3);
+
+    // the bar is already longer than the new signature, it ends on the next beat
+    tracker.set_signature(TimeSignature::new(2, 4));
+    assert_eq!(
+        positions(&mut tracker, 4),
+        ["2:1:0", "2:2:0", "3:1:0", "3:2:0"]
+    );
+}
+
+#[test]
+fn test_position_queries() {
+    let mut tracker = PositionTracker::new(1, TimeSignature::new(3, 4));
+    let every_two_bars = (0..15)
+        .map(|_| tracker.tick())
+        .enumerate()
+        .filter(|(_, position)| position.is_every_bars(2))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    assert_eq!(every_two_bars, [0, 6, 12]);
+
+    let position = BarPosition {
+        bar: 3,
+        beat: 1,
+        tick: 0,
+    };
+    assert!(position.is_beat());
+    assert!(!position.is_downbeat());
+    assert!(!position.is_every_bars(1));
+}
+
+#[tokio::test]
+async fn test_bar_clock() {
+    let now = Instant::now();
+    let mut out = Vec::new();
+
+    // 2 pulses per bar, a trigger every 2 bars, and a reset before the 8th pulse
+    run_for(
+        dg_clock::bar_clock(
+            MockClockIn::new(millis(now, &[10, 20, 30, 40, 50, 60, 70, 80, 90, 100])),
+            MockClockIn::new(millis(now, &[65])),
+            MockGateOut::new(&mut out),
+            PositionTracker::new(1, TimeSignature::new(2, 4)),
+            2,
+            Duration::from_millis(2),
+            OverlapPolicy::Drop,
+        ),
+        Duration::from_millis(110),
+    )
+    .await;
+
+    assert_pulses(now, &gate_pulses(&out), &[10, 50, 70]);
+}
@@ -0,0 +1,93 @@
+use embassy_time::{Duration, Instant};
+
+use dg_clock::{BurstPolicy, TrainMode, ratchet_offset};
+
+mod common;
+
+use common::{MockClockIn, MockClockOut, Pulse, assert_pulses, millis, run_for};
+
+async fn run_ratchet(events: &[u64], curve: f32, policy: BurstPolicy) -> (Instant, Vec<Pulse>) {
+    let now = Instant::now();
+    let mut pulses = Vec::new();
+
+    run_for(
+        dg_clock::clock_train(
+            MockClockIn::new(millis(now, events)),
+            MockClockOut::new(&mut pulses),
+            4,
+            120.0,
+            curve,
+            TrainMode::Ratchet(policy),
+        ),
+        Duration::from_millis(130),
+    )
+    .await;
+
+    (now, pulses)
+}
+
+#[test]
+fn test_ratchet_offset_curves() {
+    let period = Duration::from_millis(100);
+    let offsets = |curve| {
+        (0..=4)
+            .map(|index| ratchet_offset(period, index, 4, curve).as_micros())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(offsets(0.0), [0, 25_000, 50_000, 75_000, 100_000]);
+
+    let gaps = |offsets: Vec<u64>| offsets.windows(2).map(|w| w[1] - w[0]).collect::<Vec<_>>();
+
+    // decelerating: gaps grow, accelerating: gaps shrink
+    let decelerating = gaps(offsets(0.5));
+    assert!(
+        decelerating.windows(2).all(|w| w[0] < w[1]),
+        "{decelerating:?}"
+    );
+    let accelerating = gaps(offsets(-0.5));
+    assert!(
+        accelerating.windows(2).all(|w| w[0] > w[1]),
+        "{accelerating:?}"
+    );
+
+    // the burst always fits in the period
+    for curve in [-1.0, -0.3, 0.0, 0.3, 1.0, 5.0] {
+        assert_eq!(offsets(curve)[0], 0);
+        assert!(offsets(curve)[3] < 100_000);
+    }
+}
+
+#[tokio::test]
+async fn test_ratchet_fits_period() {
+    let (now, pulses) = run_ratchet(&[10, 50, 90], 0.0, BurstPolicy::Restart).await;
+
+    assert_pulses(
+        now,
+        &pulses,
+        &[
+            10, // period unknown, single pulse
+            50, 60, 70, 80, //
+            90, 100, 110, 120,
+        ],
+    );
+    assert_eq!(pulses[1].duration(), Duration::from_millis(5));
+}
+
+#[tokio::test]
+async fn test_ratchet_finish() {
+    let (now, pulses) = run_ratchet(&[10, 50, 78], 0.0, BurstPolicy::Finish).await;
+    assert_pulses(now, &pulses, &[10, 50, 60, 70, 80]);
+}
+
+#[tokio::test]
+async fn test_ratchet_truncate() {
+    let (now, pulses) = run_ratchet(&[10, 50, 78], 0.0, BurstPolicy::Truncate).await;
+    assert_pulses(now, &pulses, &[10, 50, 60, 70]);
+}
+
+#[tokio::test]
+async fn test_ratchet_restart() {
+    let (now, pulses) = run_ratchet(&[10, 50, 78], 0.0, BurstPolicy::Restart).await;
+    assert_pulses(now, &pulses, &[10, 50, 60, 70, 78, 85, 92, 99]);
+}
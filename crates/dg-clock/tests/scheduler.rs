@@ -0,0 +1,186 @@
+use embassy_futures::join::join;
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use embassy_time::{Duration, Instant, Timer};
+
+use dg_clock::{EventScheduler, OutputAction, ScheduledEvent, SchedulerFull};
+
+mod common;
+
+use common::{assert_instant_shortly_after, run_for};
+
+type TestScheduler = EventScheduler<NoopRawMutex, 4>;
+
+fn event(output: usize, at: Instant, action: OutputAction) -> ScheduledEvent {
+    ScheduledEvent { output, at, action }
+}
+
+async fn run_scheduler(
+    scheduler: &TestScheduler,
+    duration: Duration,
+    schedule: impl Future,
+) -> Vec<(Instant, usize, OutputAction)> {
+    let mut executed = Vec::new();
+
+    run_for(
+        join(
+            scheduler.run(async |output, action| {
+                executed.push((Instant::now(), output, action));
+            }),
+            schedule,
+        ),
+        duration,
+    )
+    .await;
+
+    executed
+}
+
+#[tokio::test]
+async fn test_scheduler_deadline_order() {
+    let scheduler = TestScheduler::new();
+    let now = Instant::now();
+    let ms = |ms| now + Duration::from_millis(ms);
+
+    scheduler
+        .schedule(event(0, ms(30), OutputAction::SetLow))
+        .unwrap();
+    scheduler
+        .schedule(event(1, ms(10), OutputAction::SetCv(1000)))
+        .unwrap();
+    scheduler
+        .schedule(event(2, ms(30), OutputAction::SetHigh))
+        .unwrap();
+    scheduler
+        .schedule(event(0, ms(20), OutputAction::SetHigh))
+        .unwrap();
+    assert_eq!(scheduler.len(), 4);
+
+    let executed = run_scheduler(&scheduler, Duration::from_millis(40), async {}).await;
+
+    // events of the same instant keep their registration order
+    let expected = [
+        (10, 1, OutputAction::SetCv(1000)),
+        (20, 0, OutputAction::SetHigh),
+        (30, 0, OutputAction::SetLow),
+        (30, 2, OutputAction::SetHigh),
+    ];
+    assert_eq!(executed.len(), expected.len(), "{executed:?}");
+    for ((instant, output, action), (at, expected_output, expected_action)) in
+        executed.iter().zip(expected)
+    {
+        assert_instant_shortly_after(*instant, ms(at));
+        assert_eq!((*output, *action), (expected_output, expected_action));
+    }
+    assert!(scheduler.is_empty());
+}
+
+#[tokio::test]
+async fn test_scheduler_earlier_event_wakes_runner() {
+    let scheduler = TestScheduler::new();
+    let now = Instant::now();
+    let ms = |ms| now + Duration::from_millis(ms);
+
+    scheduler
+        .schedule(event(0, ms(50), OutputAction::SetLow))
+        .unwrap();
+
+    // while the runner waits for the 50ms event, a pulse is registered before it
+    let executed = run_scheduler(&scheduler, Duration::from_millis(60), async {
+        Timer::at(ms(10)).await;
+        scheduler
+            .pulse(1, ms(20), Duration::from_millis(5))
+            .unwrap();
+    })
+    .await;
+
+    let expected = [
+        (20, 1, OutputAction::SetHigh),
+        (25, 1, OutputAction::SetLow),
+        (50, 0, OutputAction::SetLow),
+    ];
+    assert_eq!(executed.len(), expected.len(), "{executed:?}");
+    for ((instant, output, action), (at, expected_output, expected_action)) in
+        executed.iter().zip(expected)
+    {
+        assert_instant_shortly_after(*instant, ms(at));
+        assert_eq!((*output, *action), (expected_output, expected_action));
+    }
+}
+
+#[tokio::test]
+async fn test_scheduler_cancel_wakes_runner() {
+    let scheduler = TestScheduler::new();
+    let now = Instant::now();
+    let ms = |ms| now + Duration::from_millis(ms);
+
+    scheduler
+        .schedule(event(0, ms(20), OutputAction::SetHigh))
+        .unwrap();
+    scheduler
+        .schedule(event(1, ms(40), OutputAction::SetLow))
+        .unwrap();
+
+    // while the runner waits for the 20ms event, it is cancelled
+    let executed = run_scheduler(&scheduler, Duration::from_millis(50), async {
+        Timer::at(ms(10)).await;
+        scheduler.cancel(0);
+    })
+    .await;
+
+    assert_eq!(executed.len(), 1, "{executed:?}");
+    let (instant, output, action) = executed[0];
+    assert_instant_shortly_after(instant, ms(40));
+    assert_eq!((output, action), (1, OutputAction::SetLow));
+    assert!(scheduler.is_empty());
+}
+
+#[tokio::test]
+async fn test_scheduler_past_events() {
+    let scheduler = TestScheduler::new();
+    let now = Instant::now();
+
+    run_for(std::future::pending::<()>(), Duration::from_millis(5)).await;
+    scheduler
+        .schedule(event(0, now, OutputAction::SetHigh))
+        .unwrap();
+
+    let executed = run_scheduler(&scheduler, Duration::from_millis(10), async {}).await;
+    assert_eq!(executed.len(), 1);
+    assert_instant_shortly_after(executed[0].0, now + Duration::from_millis(5));
+}
+
+#[test]
+fn test_scheduler_capacity_and_cancel() {
+    let scheduler = TestScheduler::new();
+    let now = Instant::from_millis(0);
+    let ms = |ms| now + Duration::from_millis(ms);
+
+    scheduler
+        .pulse(0, ms(10), Duration::from_millis(5))
+        .unwrap();
+    scheduler
+        .schedule(event(1, ms(10), OutputAction::SetCv(0)))
+        .unwrap();
+
+    // a pulse needs room for both of its edges
+    assert_eq!(
+        scheduler.pulse(2, ms(20), Duration::from_millis(5)),
+        Err(SchedulerFull)
+    );
+    assert_eq!(scheduler.len(), 3);
+
+    scheduler
+        .schedule(event(2, ms(20), OutputAction::SetHigh))
+        .unwrap();
+    assert_eq!(
+        scheduler.schedule(event(3, ms(20), OutputAction::SetHigh)),
+        Err(SchedulerFull)
+    );
+
+    scheduler.cancel(0);
+    assert_eq!(scheduler.len(), 2);
+    scheduler
+        .pulse(3, ms(30), Duration::from_millis(5))
+        .unwrap();
+    assert_eq!(scheduler.len(), 4);
+}
@@ -0,0 +1,106 @@
+use embassy_time::{Duration, Instant};
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+use dg_clock::{Pattern, Step, TrigCondition};
+
+mod common;
+
+use common::{MockClockIn, MockClockOut, Pulse, assert_pulses, millis, run_for};
+
+const PATTERN: Pattern = Pattern::new(&[Step::TRIG, Step::OFF, Step::TRIG.with_ratchets(2)]);
+
+async fn run_sequencer(
+    pattern: &Pattern,
+    clocks: &[u64],
+    resets: &[u64],
+    fill: i32,
+) -> (Instant, Vec<Pulse>) {
+    let now = Instant::now();
+    let mut pulses = Vec::new();
+
+    run_for(
+        dg_clock::step_sequencer(
+            MockClockIn::new(millis(now, clocks)),
+            MockClockIn::new(millis(now, resets)),
+            MockClockOut::new(&mut pulses),
+            pattern,
+            fill,
+            SmallRng::seed_from_u64(0),
+            Duration::from_millis(2),
+        ),
+        Duration::from_millis(clocks.last().unwrap() + 15),
+    )
+    .await;
+
+    (now, pulses)
+}
+
+#[test]
+fn test_trig_conditions() {
+    let iterations = |condition: TrigCondition, fill| {
+        (0..6)
+            .map(|iteration| condition.holds(iteration, fill))
+            .collect::<Vec<_>>()
+    };
+
+    let ratio = |a, b| iterations(TrigCondition::Ratio { a, b }, false);
+    assert_eq!(ratio(1, 2), [true, false, true, false, true, false]);
+    assert_eq!(ratio(2, 2), [false, true, false, true, false, true]);
+    assert_eq!(ratio(3, 3), [false, false, true, false, false, true]);
+
+    assert_eq!(
+        iterations(TrigCondition::First, false),
+        [true, false, false, false, false, false]
+    );
+    assert_eq!(
+        iterations(TrigCondition::NotFirst, false),
+        [false, true, true, true, true, true]
+    );
+
+    assert!(TrigCondition::Fill.holds(3, true));
+    assert!(!TrigCondition::Fill.holds(3, false));
+    assert!(TrigCondition::NotFill.holds(3, false));
+    assert!(!TrigCondition::NotFill.holds(3, true));
+}
+
+#[test]
+fn test_pattern_const() {
+    assert_eq!(PATTERN.length(), 3);
+    assert_eq!(PATTERN.steps()[1], Step::OFF);
+    assert_eq!(PATTERN.steps()[2].ratchets, 2);
+}
+
+#[tokio::test]
+async fn test_sequencer_ratchets_and_reset() {
+    let (now, pulses) = run_sequencer(&PATTERN, &[10, 20, 30, 40, 50, 60, 70, 80], &[75], 0).await;
+
+    // the reset makes the last clock play the first step again
+    assert_pulses(now, &pulses, &[10, 30, 35, 40, 60, 65, 70, 80]);
+    assert_eq!(pulses[1].duration(), Duration::from_millis(2));
+}
+
+#[tokio::test]
+async fn test_sequencer_micro_timing() {
+    let pattern = Pattern::new(&[Step::TRIG.with_offset(0.3), Step::TRIG.with_offset(-0.2)]);
+    let (now, pulses) = run_sequencer(&pattern, &[10, 20, 30, 40, 50], &[], 0).await;
+
+    // offsets apply once the step length is known, late steps are played ahead of their clock
+    assert_pulses(now, &pulses, &[10, 20, 33, 38, 53, 58]);
+}
+
+#[tokio::test]
+async fn test_sequencer_conditions() {
+    let pattern = Pattern::new(&[
+        Step::TRIG.with_condition(TrigCondition::First),
+        Step::TRIG.with_condition(TrigCondition::Ratio { a: 2, b: 2 }),
+        Step::TRIG.with_condition(TrigCondition::Fill),
+        Step::TRIG.with_probability(0.0),
+    ]);
+
+    let (now, pulses) = run_sequencer(&pattern, &[10, 20, 30, 40, 50, 60, 70, 80], &[], 0).await;
+    assert_pulses(now, &pulses, &[10, 60]);
+
+    let (now, pulses) = run_sequencer(&pattern, &[10, 20, 30, 40, 50, 60, 70, 80], &[], 1).await;
+    assert_pulses(now, &pulses, &[10, 30, 60, 70]);
+}
@@ -0,0 +1,97 @@
+use embassy_time::{Duration, Instant};
+
+use dg_clock::{Staircase, StaircaseDirection};
+
+mod common;
+
+use common::{MockClockIn, MockCvOut, millis, run_for};
+
+fn steps(direction: StaircaseDirection, length: u32, count: usize) -> Vec<u32> {
+    let mut staircase = Staircase::new(direction);
+    (0..count).map(|_| staircase.step(length)).collect()
+}
+
+#[test]
+fn test_staircase_directions() {
+    assert_eq!(
+        steps(StaircaseDirection::Up, 4, 9),
+        [0, 1, 2, 3, 0, 1, 2, 3, 0]
+    );
+    assert_eq!(
+        steps(StaircaseDirection::Down, 4, 9),
+        [3, 2, 1, 0, 3, 2, 1, 0, 3]
+    );
+    assert_eq!(
+        steps(StaircaseDirection::PingPong, 4, 9),
+        [0, 1, 2, 3, 2, 1, 0, 1, 2]
+    );
+    assert_eq!(steps(StaircaseDirection::PingPong, 2, 5), [0, 1, 0, 1, 0]);
+
+    for direction in [
+        StaircaseDirection::Up,
+        StaircaseDirection::Down,
+        StaircaseDirection::PingPong,
+    ] {
+        assert_eq!(steps(direction, 1, 3), [0, 0, 0]);
+        assert_eq!(steps(direction, 0, 3), [0, 0, 0]);
+    }
+}
+
+#[test]
+fn test_staircase_length_change_and_reset() {
+    let mut staircase = Staircase::new(StaircaseDirection::Up);
+    assert_eq!(staircase.position(), None);
+
+    for _ in 0..6 {
+        staircase.step(8);
+    }
+    assert_eq!(staircase.position(), Some(5));
+
+    // the length shrinks below the current step
+    assert_eq!(staircase.step(4), 0);
+    assert_eq!(staircase.step(4), 1);
+
+    staircase.reset();
+    assert_eq!(staircase.position(), None);
+    assert_eq!(staircase.step(4), 0);
+}
+
+#[test]
+fn test_staircase_values() {
+    let mut staircase = Staircase::new(StaircaseDirection::Up);
+    assert_eq!(staircase.value(5, 1.0), 0);
+
+    let values = (0..5)
+        .map(|_| {
+            staircase.step(5);
+            staircase.value(5, 1.0)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(values, [0, 0x4000, 0x8000, 0xbfff, u16::MAX]);
+
+    assert_eq!(staircase.value(5, 0.5), 0x8000);
+    assert_eq!(staircase.value(5, 2.0), u16::MAX);
+    assert_eq!(staircase.value(1, 1.0), 0);
+}
+
+#[tokio::test]
+async fn test_staircase_clocked() {
+    let now = Instant::now();
+    let mut values = Vec::new();
+
+    run_for(
+        dg_clock::staircase(
+            MockClockIn::new(millis(now, &[10, 20, 30, 40, 50, 60])),
+            MockClockIn::new(millis(now, &[45])),
+            MockCvOut::new(&mut values),
+            Staircase::new(StaircaseDirection::PingPong),
+            3,
+            1.0,
+        ),
+        Duration::from_millis(70),
+    )
+    .await;
+
+    let values = values.iter().map(|(_, value)| *value).collect::<Vec<_>>();
+    assert_eq!(values, [0, 0x8000, u16::MAX, 0x8000, 0, 0x8000]);
+}
@@ -0,0 +1,104 @@
+use embassy_time::{Duration, Instant};
+
+use dg_clock::SwingGrid;
+
+mod common;
+
+use common::{MockClockIn, MockClockOut, assert_pulses, millis, run_for};
+
+#[tokio::test]
+async fn test_swing_sixteenth() {
+    let now = Instant::now();
+    let mut pulses = Vec::new();
+
+    run_for(
+        dg_clock::swing(
+            MockClockIn::new(millis(now, &[10, 30, 50, 70])),
+            MockClockOut::new(&mut pulses),
+            0.5,
+            SwingGrid::Sixteenth,
+            4,
+            2,
+            Duration::from_millis(2),
+        ),
+        Duration::from_millis(100),
+    )
+    .await;
+
+    assert_pulses(now, &pulses, &[10, 40, 50, 80]);
+}
+
+#[tokio::test]
+async fn test_swing_eighth_on_sixteenth_clock() {
+    let now = Instant::now();
+    let mut pulses = Vec::new();
+
+    run_for(
+        dg_clock::swing(
+            MockClockIn::new(millis(now, &[10, 20, 30, 40, 50, 60, 70, 80])),
+            MockClockOut::new(&mut pulses),
+            0.5,
+            SwingGrid::Eighth,
+            4,
+            2,
+            Duration::from_millis(2),
+        ),
+        Duration::from_millis(100),
+    )
+    .await;
+
+    // the off-grid sixteenths are passed through, moving along with their eighth
+    assert_pulses(now, &pulses, &[10, 20, 40, 45, 50, 60, 80, 85]);
+}
+
+#[tokio::test]
+async fn test_swing_squeezes_swung_step() {
+    let now = Instant::now();
+    let mut pulses = Vec::new();
+
+    // 6 PPQN: three pulses per eighth, every 10ms
+    run_for(
+        dg_clock::swing(
+            MockClockIn::new(millis(
+                now,
+                &[10, 20, 30, 40, 50, 60, 70, 80, 90, 100, 110, 120],
+            )),
+            MockClockOut::new(&mut pulses),
+            0.6,
+            SwingGrid::Eighth,
+            6,
+            2,
+            Duration::from_millis(2),
+        ),
+        Duration::from_millis(140),
+    )
+    .await;
+
+    assert_pulses(
+        now,
+        &pulses,
+        &[10, 20, 30, 58, 62, 66, 70, 80, 90, 118, 122, 126],
+    );
+}
+
+#[tokio::test]
+async fn test_swing_every_third_clamped() {
+    let now = Instant::now();
+    let mut pulses = Vec::new();
+
+    run_for(
+        dg_clock::swing(
+            MockClockIn::new(millis(now, &[10, 30, 50, 70, 90, 110])),
+            MockClockOut::new(&mut pulses),
+            2.0,
+            SwingGrid::Sixteenth,
+            4,
+            3,
+            Duration::from_millis(2),
+        ),
+        Duration::from_millis(140),
+    )
+    .await;
+
+    assert_pulses(now, &pulses, &[10, 30, 65, 70, 90, 125]);
+}
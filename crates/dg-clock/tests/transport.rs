@@ -0,0 +1,190 @@
+use embassy_futures::join::join;
+use embassy_futures::select::{Either, select};
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use embassy_time::{Duration, Instant, Timer};
+
+use dg_clock::{OverlapPolicy, Staircase, StaircaseDirection, Transport, TransportState};
+use dg_types::{CvOut, GateOut};
+
+mod common;
+
+use common::{
+    MockClockIn, MockCvOut, MockGateIn, MockGateOut, assert_pulses, gate_pulses, millis, run_for,
+    simulate,
+};
+
+type TestTransport = Transport<NoopRawMutex, 2>;
+
+#[tokio::test]
+async fn test_transport_states() {
+    let transport = TestTransport::new();
+    assert_eq!(transport.state(), TransportState::Stopped);
+
+    transport.start();
+    assert_eq!(transport.state(), TransportState::Running);
+    transport.toggle();
+    assert_eq!(transport.state(), TransportState::Stopped);
+    transport.stop();
+    assert_eq!(transport.state(), TransportState::Stopped);
+    transport.restart();
+    assert_eq!(transport.state(), TransportState::Running);
+}
+
+#[tokio::test]
+async fn test_transport_follow() {
+    let transport = TestTransport::new();
+    let now = Instant::now();
+    let mut edges = Vec::new();
+    let mut gate = MockGateOut::new(&mut edges);
+
+    let follower = transport.follow(
+        &mut gate,
+        async |gate| {
+            // 6000 BPM: a pulse every 10ms, starting a period after each start
+            dg_clock::clock(gate, 6000.0, OverlapPolicy::Drop).await;
+        },
+        async |gate| gate.set_low().await,
+    );
+
+    let control = async {
+        transport.start();
+        Timer::at(now + Duration::from_millis(33)).await;
+        transport.stop();
+        Timer::at(now + Duration::from_millis(55)).await;
+        transport.start();
+    };
+
+    run_for(join(follower, control), Duration::from_millis(82)).await;
+
+    let pulses = gate_pulses(&edges);
+    assert_pulses(now, &pulses, &[10, 20, 30, 65, 75]);
+
+    // stopping cut the running pulse short
+    pulses[2].assert_ended_shortly_after(now + Duration::from_millis(33));
+}
+
+#[tokio::test]
+async fn test_transport_restart_resets_followers() {
+    let transport = TestTransport::new();
+    let now = Instant::now();
+    let mut edges = Vec::new();
+    let mut gate = MockGateOut::new(&mut edges);
+
+    let follower = transport.follow(
+        &mut gate,
+        async |gate| dg_clock::clock(gate, 6000.0, OverlapPolicy::Drop).await,
+        async |gate| gate.set_low().await,
+    );
+
+    let control = async {
+        transport.start();
+        Timer::at(now + Duration::from_millis(15)).await;
+        transport.stop();
+        transport.start();
+    };
+
+    run_for(join(follower, control), Duration::from_millis(32)).await;
+
+    // the clock re-phased on the quick stop/start
+    assert_pulses(now, &gate_pulses(&edges), &[10, 25]);
+}
+
+#[tokio::test]
+async fn test_transport_cv_follower() {
+    let transport = TestTransport::new();
+    let now = Instant::now();
+    let ms = |ms| now + Duration::from_millis(ms);
+    let mut values = Vec::new();
+    let mut cv = MockCvOut::new(&mut values);
+
+    let follower = transport.follow(
+        &mut cv,
+        async |cv| {
+            dg_clock::staircase(
+                MockClockIn::new(millis(now, &[10, 20, 30, 40, 60, 70])),
+                MockClockIn::new([]),
+                cv,
+                Staircase::new(StaircaseDirection::Up),
+                3,
+                1.0,
+            )
+            .await;
+        },
+        async |cv| cv.set_value(0).await,
+    );
+
+    let control = async {
+        transport.start();
+        Timer::at(ms(35)).await;
+        transport.stop();
+        Timer::at(ms(50)).await;
+        transport.start();
+    };
+
+    run_for(join(follower, control), Duration::from_millis(80)).await;
+
+    // the CV goes back to zero on stop, and the staircase starts over on the next start
+    let expected = [
+        (ms(10), 0),
+        (ms(20), 0x8000),
+        (ms(30), u16::MAX),
+        (ms(35), 0),
+        (ms(60), 0),
+        (ms(70), 0x8000),
+    ];
+    assert_eq!(values, expected);
+}
+
+/// Samples the transport state at the given offsets (in milliseconds) from `now`.
+async fn sample_states(
+    transport: &TestTransport,
+    now: Instant,
+    offsets: &[u64],
+) -> Vec<TransportState> {
+    let mut states = Vec::new();
+    for ms in offsets {
+        Timer::at(now + Duration::from_millis(*ms)).await;
+        states.push(transport.state());
+    }
+    states
+}
+
+#[tokio::test]
+async fn test_transport_button() {
+    let transport = TestTransport::new();
+    let now = Instant::now();
+
+    let button = dg_clock::transport_button(&transport, MockClockIn::new(millis(now, &[10, 20])));
+    let Either::Second(states) =
+        simulate(select(button, sample_states(&transport, now, &[5, 15, 25]))).await
+    else {
+        unreachable!();
+    };
+
+    use TransportState::*;
+    assert_eq!(states, [Stopped, Running, Stopped]);
+}
+
+#[tokio::test]
+async fn test_transport_gate() {
+    let transport = TestTransport::new();
+    let now = Instant::now();
+
+    let run = MockGateIn::new([
+        (now + Duration::from_millis(10), true),
+        (now + Duration::from_millis(20), false),
+        (now + Duration::from_millis(30), true),
+    ]);
+    let gate = dg_clock::transport_gate(&transport, run);
+    let Either::Second(states) = simulate(select(
+        gate,
+        sample_states(&transport, now, &[5, 15, 25, 35]),
+    ))
+    .await
+    else {
+        unreachable!();
+    };
+
+    use TransportState::*;
+    assert_eq!(states, [Stopped, Running, Stopped, Running]);
+}
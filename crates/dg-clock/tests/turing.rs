@@ -0,0 +1,108 @@
+use embassy_time::{Duration, Instant};
+
+use dg_noise::export::SmallRng;
+use dg_noise::{NoiseGenerator, TuringMachine, WhiteNoiseGenerator};
+use rand::SeedableRng;
+
+mod common;
+
+use common::{MockClockIn, MockCvOut, MockGateOut, gate_pulses, millis, run_for};
+
+/// Noise source always producing the same sample.
+struct Constant(u16);
+
+impl NoiseGenerator for Constant {
+    fn sample(&mut self) -> u16 {
+        self.0
+    }
+}
+
+fn white_noise(seed: u64) -> WhiteNoiseGenerator<SmallRng> {
+    WhiteNoiseGenerator::new(SmallRng::seed_from_u64(seed))
+}
+
+#[test]
+fn test_turing_length_clamped() {
+    assert_eq!(TuringMachine::new(Constant(0), 0).length(), 2);
+    assert_eq!(TuringMachine::new(Constant(0), 7).length(), 7);
+    assert_eq!(TuringMachine::new(Constant(0), 20).length(), 16);
+
+    let mut machine = TuringMachine::new(Constant(0xffff), 16);
+    machine.set_length(3);
+    assert_eq!(machine.register(), 0b111);
+}
+
+#[test]
+fn test_turing_locked_loop() {
+    for length in 2..=16 {
+        let mut machine = TuringMachine::new(white_noise(length as u64), length);
+        let values = (0..3 * length)
+            .map(|_| {
+                machine.step(0.0);
+                machine.cv()
+            })
+            .collect::<Vec<_>>();
+
+        let length = length as usize;
+        assert_eq!(values[..length], values[length..2 * length]);
+        assert_eq!(values[..length], values[2 * length..]);
+    }
+}
+
+#[test]
+fn test_turing_random() {
+    const STEPS: usize = 10_000;
+
+    let mut machine = TuringMachine::new(white_noise(42), 8);
+    let mut changed = 0;
+    for _ in 0..STEPS {
+        let top = machine.gate();
+        machine.step(1.0);
+        if (machine.register() & 1 == 1) != top {
+            changed += 1;
+        }
+    }
+
+    // each recirculated bit is replaced by a random one, which differs half of the time
+    let ratio = changed as f32 / STEPS as f32;
+    assert!((0.47..0.53).contains(&ratio), "{ratio}");
+}
+
+#[test]
+fn test_turing_cv_full_range() {
+    let machine = TuringMachine::new(Constant(0b1011), 4);
+    assert_eq!(machine.cv(), 0b1011 << 12);
+
+    let machine = TuringMachine::new(Constant(0xabcd), 16);
+    assert_eq!(machine.cv(), 0xabcd);
+}
+
+#[tokio::test]
+async fn test_turing_machine_outputs() {
+    let now = Instant::now();
+    let mut edges = Vec::new();
+    let mut values = Vec::new();
+
+    run_for(
+        dg_clock::turing_machine(
+            MockClockIn::new(millis(now, &[10, 20, 30, 40])),
+            MockGateOut::new(&mut edges),
+            MockCvOut::new(&mut values),
+            TuringMachine::new(Constant(0b1010), 4),
+            0.0,
+            4,
+            Duration::from_millis(2),
+        ),
+        Duration::from_millis(50),
+    )
+    .await;
+
+    // 1010 -> 0101 -> 1010 -> ...
+    let values = values.iter().map(|(_, value)| *value).collect::<Vec<_>>();
+    assert_eq!(values, [0x5000, 0xa000, 0x5000, 0xa000]);
+
+    let pulses = gate_pulses(&edges);
+    assert_eq!(pulses.len(), 2);
+    pulses[0].assert_shortly_after(now + Duration::from_millis(20));
+    pulses[1].assert_shortly_after(now + Duration::from_millis(40));
+}
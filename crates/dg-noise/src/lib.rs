@@ -80,3 +80,78 @@ impl<R: RngCore> NoiseGenerator for RedNoiseGenerator<R> {
         output.clamp(0.0, 65535.0) as u16
     }
 }
+
+// ---
+
+/// Shortest loop supported by [`TuringMachine`].
+pub const MIN_TURING_LENGTH: u8 = 2;
+
+/// Longest loop supported by [`TuringMachine`].
+pub const MAX_TURING_LENGTH: u8 = 16;
+
+/// Looping random shift register, after the Music Thing Modular Turing Machine.
+///
+/// On each step, the bit leaving the loop is fed back into it. With some probability, it is
+/// replaced by a random bit instead, so that the loop slowly mutates.
+pub struct TuringMachine<N: NoiseGenerator> {
+    noise: N,
+    register: u16,
+    length: u8,
+}
+
+impl<N: NoiseGenerator> TuringMachine<N> {
+    /// Creates a machine with a random initial register. `length` is clamped to
+    /// [`MIN_TURING_LENGTH`]..=[`MAX_TURING_LENGTH`].
+    pub fn new(mut noise: N, length: u8) -> Self {
+        let register = noise.sample();
+        let mut machine = Self {
+            noise,
+            register,
+            length: MAX_TURING_LENGTH,
+        };
+        machine.set_length(length);
+        machine
+    }
+
+    pub fn length(&self) -> u8 {
+        self.length
+    }
+
+    /// Changes the loop length, clamped to [`MIN_TURING_LENGTH`]..=[`MAX_TURING_LENGTH`]. Bits
+    /// beyond the new length are discarded.
+    pub fn set_length(&mut self, length: u8) {
+        self.length = length.clamp(MIN_TURING_LENGTH, MAX_TURING_LENGTH);
+        self.register &= self.mask();
+    }
+
+    pub fn register(&self) -> u16 {
+        self.register
+    }
+
+    /// Shifts the register by one bit. `probability` is the chance that the recirculated bit is
+    /// replaced by a random one: 0 locks the loop, 1 makes it fully random.
+    pub fn step(&mut self, probability: f32) {
+        let mut bit = (self.register >> (self.length - 1)) & 1;
+
+        let threshold = probability.clamp(0.0, 1.0) * 65536.0;
+        if (self.noise.sample() as f32) < threshold {
+            bit = self.noise.sample() >> 15;
+        }
+
+        self.register = ((self.register << 1) | bit) & self.mask();
+    }
+
+    /// Whether the top bit of the loop is set.
+    pub fn gate(&self) -> bool {
+        (self.register >> (self.length - 1)) & 1 == 1
+    }
+
+    /// The loop scaled to the full `u16` range.
+    pub fn cv(&self) -> u16 {
+        self.register << (MAX_TURING_LENGTH - self.length)
+    }
+
+    fn mask(&self) -> u16 {
+        (u32::MAX >> (32 - self.length)) as u16
+    }
+}
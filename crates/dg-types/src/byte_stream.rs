@@ -0,0 +1,21 @@
+/// Source of bytes, e.g. a UART receiving MIDI.
+pub trait ByteIn {
+    async fn read_byte(&mut self) -> u8;
+}
+
+/// Sink of bytes, e.g. a UART sending MIDI.
+pub trait ByteOut {
+    async fn write_byte(&mut self, byte: u8);
+}
+
+impl<T: ByteIn + ?Sized> ByteIn for &mut T {
+    async fn read_byte(&mut self) -> u8 {
+        (**self).read_byte().await
+    }
+}
+
+impl<T: ByteOut + ?Sized> ByteOut for &mut T {
+    async fn write_byte(&mut self, byte: u8) {
+        (**self).write_byte(byte).await;
+    }
+}
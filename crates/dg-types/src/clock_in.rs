@@ -1,8 +1,13 @@
-use embassy_time::Instant;
+use embassy_time::{Duration, Instant};
 use embedded_hal_async::digital::Wait;
 
 pub trait ClockIn {
     async fn wait(&mut self) -> Instant;
+
+    /// Waits for the next edge for at most `timeout`, returning `None` if none arrived.
+    async fn wait_timeout(&mut self, timeout: Duration) -> Option<Instant> {
+        embassy_time::with_timeout(timeout, self.wait()).await.ok()
+    }
 }
 
 impl<T: Wait> ClockIn for T {
@@ -1,8 +1,40 @@
-use embassy_time::Duration;
+use embassy_time::{Duration, Timer};
 use embedded_hal::digital::OutputPin;
 
 pub trait ClockOut {
+    /// Emits a pulse of `duration`.
+    ///
+    /// Generators may drop this future before the pulse is over, when a new pulse overlaps it
+    /// (see `OverlapPolicy` in dg-clock). The output must then be left as it is: the generator
+    /// follows up with [`ClockOut::end_pulse`] to cut the pulse short, or with
+    /// [`ClockOut::hold_pulse`] to lengthen it.
     async fn emit_pulse(&mut self, duration: Duration);
+
+    /// Ends a pulse whose [`ClockOut::emit_pulse`] future was dropped.
+    ///
+    /// Outputs without a level, e.g. MIDI clocks, have nothing to do.
+    async fn end_pulse(&mut self) {}
+
+    /// Keeps a pulse whose [`ClockOut::emit_pulse`] future was dropped going for `duration`, then
+    /// ends it. This future may be dropped as well.
+    async fn hold_pulse(&mut self, duration: Duration) {
+        Timer::after(duration).await;
+        self.end_pulse().await;
+    }
+}
+
+impl<T: ClockOut + ?Sized> ClockOut for &mut T {
+    async fn emit_pulse(&mut self, duration: Duration) {
+        (**self).emit_pulse(duration).await;
+    }
+
+    async fn end_pulse(&mut self) {
+        (**self).end_pulse().await;
+    }
+
+    async fn hold_pulse(&mut self, duration: Duration) {
+        (**self).hold_pulse(duration).await;
+    }
 }
 
 /// Newtype wrapper for a pin to implement `ClockOut`.
@@ -11,7 +43,11 @@ pub struct Pin<T>(pub T);
 impl<T: OutputPin> ClockOut for Pin<T> {
     async fn emit_pulse(&mut self, duration: Duration) {
         self.0.set_high().unwrap();
-        embassy_time::Timer::after(duration).await;
+        Timer::after(duration).await;
+        self.0.set_low().unwrap();
+    }
+
+    async fn end_pulse(&mut self) {
         self.0.set_low().unwrap();
     }
 }
@@ -25,6 +61,15 @@ where
         ::embassy_futures::join::join(self.0.emit_pulse(duration), self.1.emit_pulse(duration))
             .await;
     }
+
+    async fn end_pulse(&mut self) {
+        ::embassy_futures::join::join(self.0.end_pulse(), self.1.end_pulse()).await;
+    }
+
+    async fn hold_pulse(&mut self, duration: Duration) {
+        ::embassy_futures::join::join(self.0.hold_pulse(duration), self.1.hold_pulse(duration))
+            .await;
+    }
 }
 
 impl<T0, T1, T2> ClockOut for (T0, T1, T2)
@@ -41,4 +86,18 @@ where
         )
         .await;
     }
+
+    async fn end_pulse(&mut self) {
+        ::embassy_futures::join::join3(self.0.end_pulse(), self.1.end_pulse(), self.2.end_pulse())
+            .await;
+    }
+
+    async fn hold_pulse(&mut self, duration: Duration) {
+        ::embassy_futures::join::join3(
+            self.0.hold_pulse(duration),
+            self.1.hold_pulse(duration),
+            self.2.hold_pulse(duration),
+        )
+        .await;
+    }
 }
@@ -0,0 +1,10 @@
+/// Control voltage input, read as a raw value spanning the whole input range.
+pub trait CvIn {
+    async fn get_value(&mut self) -> u16;
+}
+
+impl<T: CvIn + ?Sized> CvIn for &mut T {
+    async fn get_value(&mut self) -> u16 {
+        (**self).get_value().await
+    }
+}
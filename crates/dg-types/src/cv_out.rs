@@ -0,0 +1,10 @@
+/// Control voltage output, set as a raw value spanning the whole output range.
+pub trait CvOut {
+    async fn set_value(&mut self, value: u16);
+}
+
+impl<T: CvOut + ?Sized> CvOut for &mut T {
+    async fn set_value(&mut self, value: u16) {
+        (**self).set_value(value).await;
+    }
+}
@@ -7,3 +7,9 @@ impl FloatParameter for f32 {
         *self
     }
 }
+
+impl<T: FloatParameter + ?Sized> FloatParameter for &mut T {
+    async fn get(&mut self) -> f32 {
+        (**self).get().await
+    }
+}
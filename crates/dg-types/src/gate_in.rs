@@ -0,0 +1,17 @@
+use embedded_hal_async::digital::Wait;
+
+/// Input whose level can be awaited, e.g. a run or hold gate.
+pub trait GateIn {
+    async fn wait_for_high(&mut self);
+    async fn wait_for_low(&mut self);
+}
+
+impl<T: Wait> GateIn for T {
+    async fn wait_for_high(&mut self) {
+        Wait::wait_for_high(self).await.unwrap();
+    }
+
+    async fn wait_for_low(&mut self) {
+        Wait::wait_for_low(self).await.unwrap();
+    }
+}
@@ -0,0 +1,29 @@
+use embedded_hal::digital::OutputPin;
+
+use crate::Pin;
+
+/// Output whose level can be directly controlled, e.g. to hold a gate high.
+pub trait GateOut {
+    async fn set_high(&mut self);
+    async fn set_low(&mut self);
+}
+
+impl<T: OutputPin> GateOut for Pin<T> {
+    async fn set_high(&mut self) {
+        self.0.set_high().unwrap();
+    }
+
+    async fn set_low(&mut self) {
+        self.0.set_low().unwrap();
+    }
+}
+
+impl<T: GateOut + ?Sized> GateOut for &mut T {
+    async fn set_high(&mut self) {
+        (**self).set_high().await;
+    }
+
+    async fn set_low(&mut self) {
+        (**self).set_low().await;
+    }
+}
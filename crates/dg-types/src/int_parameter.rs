@@ -9,3 +9,9 @@ impl IntParameter for i32 {
         *self
     }
 }
+
+impl<T: IntParameter + ?Sized> IntParameter for &mut T {
+    async fn get(&mut self) -> i32 {
+        (**self).get().await
+    }
+}
@@ -1,14 +1,24 @@
 #![no_std]
 #![allow(async_fn_in_trait)]
 
+mod byte_stream;
 mod clock_in;
 mod clock_out;
+mod cv_in;
+mod cv_out;
 mod float_parameter;
+mod gate_in;
+mod gate_out;
 mod int_parameter;
 
 pub use self::{
+    byte_stream::{ByteIn, ByteOut},
     clock_in::ClockIn,
     clock_out::{ClockOut, Pin},
+    cv_in::CvIn,
+    cv_out::CvOut,
     float_parameter::FloatParameter,
+    gate_in::GateIn,
+    gate_out::GateOut,
     int_parameter::IntParameter,
 };